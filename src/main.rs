@@ -1,91 +1,159 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
+#![cfg_attr(test, allow(dead_code, unused_imports))]
 #![allow(unused_parens)]
 
 use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use cortex_m_rt::{entry, exception};
-// use cortex_m::interrupt::{Mutex};
+use cortex_m_rt::{entry, exception, interrupt};
+#[cfg(not(test))]
 use panic_halt as _;
 use peripherals::{
-    afio::{EXTIx_Px, AFIO}, exti::exti, gpio::{GpioXBase, Gpio}, i2c::{I2C, I2C_BASE, PCF8574_LCD}, nvic::{NVIC, NVIC_BASE}, rcc::{rcc, IOPxEN, TIMxEN}
+    exti::Edge, gpio::{PullUp, Speed}, i2c::{I2cError, Mode as I2cMode, PCF8574_LCD}, interrupt::{interrupt, Interrupt}, rcc::{notify_clocks_changed, ClockConfig, ClockDependent, Mco, Peripheral}, tim_gp::TIM2, Peripherals
 };
 use rtt_target::{rprintln, rtt_init_print};
-use utils::delay::delay_sys_clk_ms;
+use utils::{
+    button::{Button, ButtonEvent, ButtonEventKind, EventQueue},
+    delay::delay_sys_clk_ms,
+    lock::PriorityCell,
+    shared::Shared,
+};
 
 mod peripherals;
 mod utils;
 
 // const
 const PCF8574_ADDRESS: u8 = 0b100111;
-const EXTI_BASE: u32 = 0x4001_0400;
-const AFIO_BASE: u32 = 0x4001_0000;
 
-// static
-static COUNT: AtomicU32 = AtomicU32::new(0);
-static REFRESH_LCD: AtomicBool = AtomicBool::new(true);
+// ST-LINK MCO 8 MHz (HSE bypass) x9 = 72 MHz, APB1 36 MHz, ADC 72 / 6 = 12 MHz
+// MCO 가 연결되지 않았거나 CSS 가 HSE 고장을 감지하면 HSI/2 x16 = 64 MHz 로 fallback
+const CLOCK_CONFIG: ClockConfig = ClockConfig::new()
+    .use_hse_bypass(8_000_000)
+    .sysclk(72_000_000)
+    .pclk1(36_000_000)
+    .adcclk(12_000_000)
+    .mco(MCO);
+
+// bring-up: PA8 (D7) 에서 PLL/2 출력으로 클럭 설정을 scope 로 확인 (72 MHz 이면 36 MHz)
+// D7 을 쓰는 shield 와 충돌하므로 `--features mco-debug` 일 때만
+#[cfg(feature = "mco-debug")]
+const MCO: Mco = Mco::PllDiv2;
+#[cfg(not(feature = "mco-debug"))]
+const MCO: Mco = Mco::NoClock;
+
+const B1_ID: u8 = 1;
+
+// interrupt priority (0 이 가장 높음). B1 debounce 상태는 두 핸들러가 모두 건드리므로 ceiling 은 둘 중 높은 쪽
+const TICK_PRIORITY: u8 = 2;
+const B1_EXTI_PRIORITY: u8 = 3;
 
+// static
+static CLOCK_CHANGED: AtomicBool = AtomicBool::new(false);
+/// TIM2 update (1 kHz) 마다 증가하는 ms counter
+static TICK_MS: AtomicU32 = AtomicU32::new(0);
+static TICK_TIMER: Shared<TIM2> = Shared::new();
+// SAFETY: TIM2 (TICK_PRIORITY), EXTI15_10 (B1_EXTI_PRIORITY), main 에서만 접근
+static B1: PriorityCell<Option<Button<'C', 13, PullUp>>, TICK_PRIORITY> = unsafe { PriorityCell::new(None) };
+static BUTTON_EVENTS: EventQueue<ButtonEvent, 8> = EventQueue::new();
+
+#[cfg(not(test))]
 #[entry]
 fn main() -> ! {
     rtt_init_print!();
-    let rcc = rcc::new();
-    let gpio_a = Gpio::new(GpioXBase::A);
-    let gpio_b = Gpio::new(GpioXBase::B);
-    let gpio_c = Gpio::new(GpioXBase::C);
-    let afio = AFIO::new(AFIO_BASE);
-    let exti = exti::new(EXTI_BASE);
-    let nvic: NVIC = NVIC::new(NVIC_BASE);
+    let dp = Peripherals::take().unwrap();
+    let rcc = dp.rcc;
+    // 다음 리셋 때 남지 않도록 읽은 뒤 바로 클리어
+    let reset_flags = rcc.take_reset_flags();
+    rprintln!("Reset cause: {} (CSR flags: {:#010x})", reset_flags.cause().as_str(), reset_flags.bits());
+    let gpio_a = dp.gpioa;
+    let gpio_b = dp.gpiob;
+    let gpio_c = dp.gpioc;
+    let afio = dp.afio;
+    let exti = dp.exti;
+    let nvic = dp.nvic;
     // Enable GPIOA, GPIOB and GPIOC clocks
     rcc.cr_hsion();
-    // rcc.set_sys_clock_32MHz();
-    rcc.set_sys_clock_64MHz();
+    let clocks = rcc.freeze(CLOCK_CONFIG).unwrap();
+
 
     let cr_val = rcc.read_cr();
     rprintln!("CR: {}", cr_val);
     let cfgr_val = rcc.read_cfgr();
     rprintln!("CFGR: {}", cfgr_val);
 
-    rcc.cfgr_adcpre(11); // ADC prescaler 8
+    rcc.enable_clock(Peripheral::ADC1);
 
-    rcc.APB2ENR_ADC1EN(true);
 
+    rcc.enable_clock(Peripheral::TIM2);
+    rcc.enable_clock(Peripheral::I2C2);
+    rcc.enable_clock(Peripheral::GPIOA);
+    rcc.enable_clock(Peripheral::GPIOB);
+    rcc.enable_clock(Peripheral::GPIOC);
 
-    rcc.APB1ENR_TIMxEN(TIMxEN::TIM2EN, true);
-    rcc.ABP1ENR_I2C2EN(true);
-    rcc.APB2ENR_IOPx_EN(IOPxEN::IOPAEN, true);
-    rcc.APB2ENR_IOPx_EN(IOPxEN::IOPBEN, true);
-    rcc.APB2ENR_IOPx_EN(IOPxEN::IOPCEN, true);
+    rcc.enable_clock(Peripheral::AFIO);
 
-    rcc.ABP2ENR_AFIOEN(true);
+    // MCO 소스는 CLOCK_CONFIG 로 freeze 가 설정 (CSS 를 켜기 전에 CFGR 를 모두 써야 한다)
+    #[cfg(feature = "mco-debug")]
+    let _mco = gpio_a.p8.into_alternate_push_pull(Speed::Mhz50);
 
-    gpio_a.crl_port_config(5, 0b0001); // Configure GPIOA pin 5 as output push-pull
-    gpio_b.crh_port_config(10, 0b1001); // Configure GPIOC pin 10 as output open-drain
-    gpio_b.crh_port_config(11, 0b1010); // Configure GPIOC pin 11 as output open-drain
+    let _led = gpio_a.p5.into_push_pull_output(Speed::Mhz10); // LD2
+    let i2c2_pins = (
+        gpio_b.p10.into_alternate_open_drain(Speed::Mhz2), // I2C2 SCL
+        gpio_b.p11.into_alternate_open_drain(Speed::Mhz2), // I2C2 SDA
+    );
 
-    gpio_c.crh_port_config(13, 0b0100); // PC13 is input mode
+    let button = gpio_c.p13.into_pull_up_input(); // B1 (active low, 외부 pull-up 과 같은 방향)
 
-    let sysclk = get_sys_clock();
-    rprintln!("System clock: {} Hz", sysclk);
+    let mut sysclk = clocks.sysclk();
+    rprintln!("Clock source: {:?}", clocks.source());
+    rprintln!(
+        "System clock: {} Hz, PCLK1: {} Hz, PCLK2: {} Hz, ADC: {} Hz",
+        sysclk,
+        clocks.pclk1(),
+        clocks.pclk2(),
+        clocks.adcclk()
+    );
    
     // PC0 ADC12_IN10  PC1 ADC12_IN11
 
     // interrupt configuration
 
-    afio.exti_cr_x(EXTIx_Px::PC, 13); // Configure EXTI13 to use PC13
-    exti.imr_set(13, true); // Enable interrupt mask register for EXTI13
-    exti.ftsr_set(13, true); // Enable rising edge trigger on EXTI13
-                             // exti.rstr_set(13, true);
-    nvic.enable_interrupt(40); // Enable EXTI15_10 interrupt
-
-    let i2c2 = I2C::new(I2C_BASE::BASE_I2C2);
-    i2c2.init();
-    rprintln!("I2C2 initialized");
-    let lcd = PCF8574_LCD::new(i2c2, PCF8574_ADDRESS);
-    lcd.lcd_initialize();
-    rprintln!("LCD initialized");
+    // B1: 1 ms tick 으로 debounce, press / release 모두 edge 를 받는다
+    let tim2 = dp.tim2;
+    tim2.start_tick(clocks.pclk1_tim(), 1_000).unwrap();
+    TICK_TIMER.put(tim2);
+    nvic.set_priority(Interrupt::TIM2, TICK_PRIORITY);
+    nvic.enable(Interrupt::TIM2);
+
+    nvic.set_priority(Interrupt::EXTI15_10, B1_EXTI_PRIORITY);
+    exti.on_line(13, on_b1_edge);
+    exti.listen(&button, Edge::Both, &afio, &nvic); // PC13 → EXTI13 → EXTI15_10
+    B1.lock(|b1| *b1 = Some(Button::new(B1_ID, button, true)));
+
+    let i2c2 = dp.i2c2;
+    // PCF8574 는 100 kHz 까지만 지원. BUSY 면 PB10 / PB11 로 버스 복구
+    // 버스를 풀지 못해도 B1, tick, CSS 는 이미 동작 중이므로 LCD 없이 계속한다
+    let lcd = match i2c2.init(&clocks, I2cMode::Standard, &i2c2_pins) {
+        Ok(()) => {
+            rprintln!("I2C2 initialized");
+            let lcd = PCF8574_LCD::new(i2c2, PCF8574_ADDRESS);
+            match lcd.lcd_initialize().and_then(|()| show_reset_cause(&lcd, reset_flags.cause().as_str())) {
+                Ok(()) => rprintln!("LCD initialized"),
+                Err(e) => rprintln!("LCD initialization failed: {:?}", e),
+            }
+            Some(lcd)
+        }
+        Err(e) => {
+            rprintln!("I2C2 initialization failed, running without LCD: {:?}", e);
+            None
+        }
+    };
+    delay_sys_clk_ms(2000);
 
     let pllrdy = rcc.read_cr_pllrdy();
     rprintln!("PLL ready: {}", pllrdy);
     let mut loop_count = 0;
+    let mut count = 0;
+    let mut refresh_lcd = true;
     // lcd.print("Hell");
 
 
@@ -94,111 +162,109 @@ fn main() -> ! {
 
 
     loop {
-        let count = COUNT.load(Ordering::Relaxed).into();
-        if REFRESH_LCD.load(Ordering::Relaxed) {
-            REFRESH_LCD.store(false, Ordering::Relaxed);
-            lcd.set_cursor(0, 0);
-            lcd.clear();
-            
-            delay_sys_clk_ms(100);
-            lcd.set_cursor(0, 0);
-            delay_sys_clk_ms(100);
-            lcd.print("Hello");
-            lcd.set_cursor(0, 6);
-            lcd.print_number(loop_count);
-            delay_sys_clk_ms(100);
-            lcd.set_cursor(1, 2);
-            delay_sys_clk_ms(100);
-            lcd.print_number(count);
-            delay_sys_clk_ms(100);
+        if CLOCK_CHANGED.swap(false, Ordering::Relaxed) {
+            let clocks = rcc.read_clocks();
+            sysclk = clocks.sysclk();
+            if let Some(lcd) = &lcd {
+                notify_clocks_changed(&clocks, &[lcd]);
+            }
+            TICK_TIMER.with(|tim2| tim2.clocks_changed(&clocks));
+            rprintln!("HSE failure, clock source: {:?}, System clock: {} Hz", clocks.source(), sysclk);
+        }
+        while let Some(event) = BUTTON_EVENTS.pop() {
+            rprintln!("Button {}: {:?}", event.id, event.kind);
+            match event.kind {
+                ButtonEventKind::Press => count += 1,
+                ButtonEventKind::LongPress => count = 0,
+                ButtonEventKind::Release | ButtonEventKind::DoubleClick => continue,
+            }
+            refresh_lcd = true;
+        }
+        if refresh_lcd {
+            // 실패하면 다음 loop 에서 다시 그린다
+            refresh_lcd = false;
+            if let Some(lcd) = &lcd {
+                if let Err(e) = show_counts(lcd, loop_count, count) {
+                    rprintln!("LCD refresh failed: {:?}", e);
+                    refresh_lcd = true;
+                }
+            }
         } else {
             loop_count += 1;
-            lcd.set_cursor(0, 6);
-            lcd.print_number(loop_count);
+            if let Some(lcd) = &lcd {
+                if let Err(e) = lcd.set_cursor(0, 6).and_then(|()| lcd.print_number(loop_count)) {
+                    rprintln!("LCD update failed: {:?}", e);
+                }
+            }
             cortex_m::asm::delay(sysclk);
             // delay_sys_clk_ms(1000);
-            // REFRESH_LCD.store(true, Ordering::Relaxed);
         }
     }
 }
 
-#[exception]
-unsafe fn DefaultHandler(irqn: i16) {
-    rprintln!("Unhandled exception (IRQn = {})", irqn);
-    let exti = exti::new(EXTI_BASE);
-    let pr_13 = exti.pr_read(13);
-    if (irqn == 40) && pr_13 {
-        rprintln!("EXTI13 interrupt");
-        COUNT.fetch_add(1, Ordering::Relaxed);
-        REFRESH_LCD.store(true, Ordering::Relaxed);
-        exti.pr_clear(13);
-    }
+fn show_reset_cause(lcd: &PCF8574_LCD, cause: &str) -> Result<(), I2cError> {
+    lcd.set_cursor(0, 0)?;
+    lcd.print("Reset:")?;
+    lcd.set_cursor(1, 0)?;
+    lcd.print(cause)
 }
 
+fn show_counts(lcd: &PCF8574_LCD, loop_count: u32, count: u32) -> Result<(), I2cError> {
+    lcd.set_cursor(0, 0)?;
+    lcd.clear()?;
+
+    delay_sys_clk_ms(100);
+    lcd.set_cursor(0, 0)?;
+    delay_sys_clk_ms(100);
+    lcd.print("Hello")?;
+    lcd.set_cursor(0, 6)?;
+    lcd.print_number(loop_count)?;
+    delay_sys_clk_ms(100);
+    lcd.set_cursor(1, 2)?;
+    delay_sys_clk_ms(100);
+    lcd.print_number(count)?;
+    delay_sys_clk_ms(100);
+    Ok(())
+}
 
-fn get_sys_clock() -> u32 {
-    // RCC 레지스터 하드코딩된 값
-    let rcc = rcc::new();
-    let rcc_cr = rcc.read_cr(); // 예시 값, 실제 값으로 교체 필요
-    let rcc_cfgr = rcc.read_cfgr(); // 예시 값, 실제 값으로 교체 필요
-
-    // HSI 클럭 속도
-    let hsi_clk = 8_000_000; // 8 MHz
-    // HSE 클럭 속도 (기본값)
-    let hse_clk = 8_000_000; // 8 MHz
+/// Clock Security System: HSE 고장 시 HSI PLL 로 복구하고 main 에 알림 <br/>
+/// NMI 는 critical section 으로 막을 수 없으므로 `utils::shared::Shared` 대신 `steal` 을 쓴다. <br/>
+/// CSS 는 `freeze` 가 CR / CFGR 쓰기 (MCO 포함) 를 모두 마친 뒤 켜고, 그 뒤 main 은 RCC 를 <br/>
+/// `read_clocks` 로 읽거나 bit-band 로 clock enable 비트만 쓰므로 CR/CFGR/CIR 쓰기가 겹치지 않는다.
+#[cfg(not(test))]
+#[exception]
+unsafe fn NonMaskableInt() {
+    let rcc = Peripherals::steal().rcc;
+    if rcc.css_recover(CLOCK_CONFIG).is_some() {
+        CLOCK_CHANGED.store(true, Ordering::Relaxed);
+    }
+}
 
-    // SWS 비트 확인 (시스템 클럭 소스)
-    let sws = (rcc_cfgr >> 2) & 0b11;
+#[cfg(not(test))]
+#[exception]
+unsafe fn DefaultHandler(irqn: i16) {
+    rprintln!("Unhandled exception (IRQn = {})", irqn);
+}
 
-    let sysclk = match sws {
-        0b00 => {
-            // HSI 사용
-            hsi_clk
+/// B1 (PC13) edge. EXTI15_10 핸들러가 pending 을 클리어한 뒤 호출한다.
+fn on_b1_edge() {
+    let now = TICK_MS.load(Ordering::Relaxed);
+    B1.lock(|b1| {
+        if let Some(b1) = b1 {
+            b1.edge(now);
         }
-        0b01 => {
-            // HSE 사용
-            hse_clk
-        }
-        0b10 => {
-            // PLL 사용
-            // PLL 소스 확인 (PLLSRC 비트)
-            let pllsrc = (rcc_cfgr >> 16) & 0b1;
-            let pll_clk_in = if pllsrc == 0 {
-                // HSI/2
-                hsi_clk / 2
-            } else {
-                // HSE
-                if (rcc_cfgr >> 17) & 0b1 == 1 {
-                    hse_clk / 2
-                } else {
-                    hse_clk
-                }
-            };
+    });
+}
 
-            // PLL 곱셈 계수 확인 (PLLMUL 비트)
-            let pllmul = ((rcc_cfgr >> 18) & 0b1111) + 2;
-            pll_clk_in * pllmul
+/// 1 ms tick: debounce 상태 갱신, 확정된 이벤트는 `BUTTON_EVENTS` 로
+#[cfg(not(test))]
+#[interrupt]
+fn TIM2() {
+    TICK_TIMER.with(|tim2| tim2.sr_uif_clear());
+    let now = TICK_MS.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+    B1.lock(|b1| {
+        if let Some(b1) = b1 {
+            b1.update(now, &BUTTON_EVENTS);
         }
-        _ => {
-            // 예약된 값 (사용되지 않음)
-            0
-        }
-    };
-
-    // AHB 프리스케일러 확인 (HPRE 비트)
-    let hpre = (rcc_cfgr >> 4) & 0b1111;
-    let ahb_prescaler = match hpre {
-        0b0000 => 1,
-        0b1000 => 2,
-        0b1001 => 4,
-        0b1010 => 8,
-        0b1011 => 16,
-        0b1100 => 64,
-        0b1101 => 128,
-        0b1110 => 256,
-        0b1111 => 512,
-        _ => 1,
-    };
-
-    sysclk / ahb_prescaler
-}
\ No newline at end of file
+    });
+}
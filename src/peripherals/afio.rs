@@ -1,8 +1,30 @@
 #![allow(non_snake_case)]
+use core::cell::Cell;
 
+use crate::peripherals::{i2c::I2c1Pins, register::Reg};
+
+pub const AFIO_BASE: u32 = 0x4001_0000;
+
+/// MAPR SWJ_CFG[26:24]: write-only, 읽은 값은 정의되지 않음
+const MAPR_SWJ_CFG: u32 = 0b111 << 24;
+
+/// ## SwjCfg - MAPR SWJ_CFG 디버그 포트 핀 배치
+/// - **Full**: 000, JTAG + SWD (리셋 값) <br/>
+/// - **FullNoNjtrst**: 001, PB4 (NJTRST) 를 GPIO 로 <br/>
+/// - **SwdOnly**: 010, JTAG 끄고 SWD 만. PA15, PB3, PB4 를 GPIO 로 <br/>
+/// - **Disabled**: 100, SWD 까지 끔. PA13, PA14 도 GPIO 로 (이후 디버거 연결 불가) <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SwjCfg {
+    Full = 0b000,
+    FullNoNjtrst = 0b001,
+    SwdOnly = 0b010,
+    Disabled = 0b100,
+}
 
 pub struct AFIO {
     base: u32,
+    /// 마지막으로 쓴 SWJ_CFG. MAPR 를 다시 쓸 때 읽은 값 대신 사용한다
+    swj_cfg: Cell<SwjCfg>,
 }
 
 
@@ -16,52 +38,159 @@ pub enum EXTIx_Px {
     PG = 0b0110,
 }
 
+impl EXTIx_Px {
+    /// GPIO 포트 문자 ('A'..='G') 에 해당하는 EXTICR 값
+    pub const fn from_port(port: char) -> EXTIx_Px {
+        match port {
+            'A' => EXTIx_Px::PA,
+            'B' => EXTIx_Px::PB,
+            'C' => EXTIx_Px::PC,
+            'D' => EXTIx_Px::PD,
+            'E' => EXTIx_Px::PE,
+            'F' => EXTIx_Px::PF,
+            'G' => EXTIx_Px::PG,
+            _ => panic!("GPIO port must be 'A'..='G'"),
+        }
+    }
+}
+
 impl AFIO {
-    pub fn new(base: u32) -> AFIO {
+    pub(super) fn new(base: u32) -> AFIO {
         AFIO {
             base: base,
+            swj_cfg: Cell::new(SwjCfg::Full),
         }
     }
-    fn EVCR(&self) -> *mut u32 {
-        (self.base) as *mut u32
+    fn EVCR(&self) -> Reg {
+        Reg::at(self.base, 0x00)
+    }
+    fn MAPR(&self) -> Reg {
+        Reg::at(self.base, 0x04)
     }
-    fn MAPR(&self) -> *mut u32 {
-        (self.base + 0x04) as *mut u32
+    fn EXTICR1(&self) -> Reg {
+        Reg::at(self.base, 0x08)
     }
-    fn EXTICR1(&self) -> *mut u32 {
-        (self.base + 0x08) as *mut u32
+    fn EXTICR2(&self) -> Reg {
+        Reg::at(self.base, 0x0C)
     }
-    fn EXTICR2(&self) -> *mut u32 {
-        (self.base + 0x0C) as *mut u32
+    fn EXTICR3(&self) -> Reg {
+        Reg::at(self.base, 0x10)
     }
-    fn EXTICR3(&self) -> *mut u32 {
-        (self.base + 0x10) as *mut u32
+    fn EXTICR4(&self) -> Reg {
+        Reg::at(self.base, 0x14)
     }
-    fn EXTICR4(&self) -> *mut u32 {
-        (self.base + 0x14) as *mut u32
+    fn MAPR2(&self) -> Reg {
+        Reg::at(self.base, 0x1C)
     }
-    fn MAPR2(&self) -> *mut u32 {
-        (self.base + 0x1C) as *mut u32
+    /// ## mapr_modify
+    /// MAPR read-modify-write. SWJ_CFG 는 읽은 값을 그대로 돌려 쓰면 SWD 가 꺼질 수 있으므로 <br/>
+    /// `mapr_swj_cfg` 로 마지막에 설정한 값 (기본 리셋 값 000) 을 쓴다. bit-band 도 내부적으로 read-modify-write 이므로 쓰지 않는다.
+    fn mapr_modify(&self, f: impl FnOnce(u32) -> u32) {
+        let swj_cfg = (self.swj_cfg.get() as u32) << 24;
+        self.MAPR().modify(|v| (f(v & !MAPR_SWJ_CFG) & !MAPR_SWJ_CFG) | swj_cfg);
+    }
+
+    /// ## mapr_swj_cfg - MAPR SWJ_CFG[26:24]
+    /// JTAG / SWD 핀을 GPIO 로 풀어 준다. 이후 다른 remap 도 이 값을 유지한다.
+    /// ```ignore
+    /// afio.mapr_swj_cfg(SwjCfg::SwdOnly); // PA15, PB3, PB4 사용
+    /// ```
+    pub fn mapr_swj_cfg(&self, cfg: SwjCfg) {
+        self.swj_cfg.set(cfg);
+        self.mapr_modify(|mapr| mapr);
     }
+
+    /// ## mapr_i2c1_remap - MAPR I2C1_REMAP (bit 1)
+    /// AF open-drain 으로 설정한 핀 쌍을 받아 I2C1 을 그 핀에 연결한다. AFIO 클럭이 켜져 있어야 한다.
+    /// ```ignore
+    /// let scl = gpio_b.p8.into_alternate_open_drain(Speed::Mhz2); // D15
+    /// let sda = gpio_b.p9.into_alternate_open_drain(Speed::Mhz2); // D14
+    /// let pins = (scl, sda);
+    /// afio.mapr_i2c1_remap(&pins);
+    /// ```
+    pub fn mapr_i2c1_remap<PINS: I2c1Pins>(&self, _pins: &PINS) {
+        self.mapr_modify(|mapr| if PINS::REMAP { mapr | (1 << 1) } else { mapr & !(1 << 1) });
+    }
+
     /// Configure the external interrupt line
     /// EXTIx external interrupt 
     pub fn exti_cr_x(&self, port: EXTIx_Px, pin: u8) {
-        unsafe {
-            let exticr = match pin {
-                0..=3 => self.EXTICR1(),
-                4..=7 => self.EXTICR2(),
-                8..=11 => self.EXTICR3(),
-                12..=15 => self.EXTICR4(),
-                _ => self.EXTICR1(),
-            };
-
-            let mut exticr_val = exticr.read_volatile();
-            let shift = (pin % 4) * 4;
-            exticr_val &= !(0b1111 << shift);
-            exticr_val |= (port as u32) << shift;
-            // rprintln!( "EXTICR: {:#010b}", exticr_val);
-            exticr.write_volatile(exticr_val);
-        }
+        let exticr = match pin {
+            0..=3 => self.EXTICR1(),
+            4..=7 => self.EXTICR2(),
+            8..=11 => self.EXTICR3(),
+            12..=15 => self.EXTICR4(),
+            _ => self.EXTICR1(),
+        };
 
+        let shift = (pin % 4) * 4;
+        exticr.write_field(shift, 0b1111, port as u32);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+
+    #[test]
+    fn i2c1_remap_follows_pins_and_keeps_swj_cfg_zero() {
+        use crate::peripherals::{gpio::Speed, Peripherals};
+        mock::reset();
+        let gpiob = unsafe { Peripherals::steal() }.gpiob;
+        let afio = AFIO::new(AFIO_BASE);
+        // SWJ_CFG 는 읽으면 쓰레기 값
+        mock::poke(AFIO_BASE + 0x04, 0x0500_0001);
+        let arduino = (
+            gpiob.p8.into_alternate_open_drain(Speed::Mhz2),
+            gpiob.p9.into_alternate_open_drain(Speed::Mhz2),
+        );
+        afio.mapr_i2c1_remap(&arduino);
+        assert_eq!(mock::peek(AFIO_BASE + 0x04), 0b11);
+        let default = (
+            gpiob.p6.into_alternate_open_drain(Speed::Mhz2),
+            gpiob.p7.into_alternate_open_drain(Speed::Mhz2),
+        );
+        afio.mapr_i2c1_remap(&default);
+        assert_eq!(mock::peek(AFIO_BASE + 0x04), 0b01);
+    }
+
+    #[test]
+    fn remap_keeps_released_jtag_pins() {
+        use crate::peripherals::{gpio::Speed, Peripherals};
+        mock::reset();
+        let gpiob = unsafe { Peripherals::steal() }.gpiob;
+        let afio = AFIO::new(AFIO_BASE);
+        afio.mapr_swj_cfg(SwjCfg::SwdOnly);
+        assert_eq!(mock::peek(AFIO_BASE + 0x04), 0b010 << 24);
+        mock::poke(AFIO_BASE + 0x04, 0); // SWJ_CFG 는 읽으면 쓰레기 값
+        let arduino = (
+            gpiob.p8.into_alternate_open_drain(Speed::Mhz2),
+            gpiob.p9.into_alternate_open_drain(Speed::Mhz2),
+        );
+        afio.mapr_i2c1_remap(&arduino);
+        assert_eq!(mock::peek(AFIO_BASE + 0x04), (0b010 << 24) | 0b10);
+    }
+
+    #[test]
+    fn exti_cr_x_routes_pc13_in_exticr4() {
+        mock::reset();
+        mock::poke(AFIO_BASE + 0x14, 0xFFFF);
+        AFIO::new(AFIO_BASE).exti_cr_x(EXTIx_Px::PC, 13);
+        assert_eq!(mock::peek(AFIO_BASE + 0x14), 0xFF2F);
+    }
+
+    #[test]
+    fn exti_cr_x_selects_register_by_pin() {
+        mock::reset();
+        let afio = AFIO::new(AFIO_BASE);
+        afio.exti_cr_x(EXTIx_Px::PB, 0);
+        afio.exti_cr_x(EXTIx_Px::PD, 7);
+        afio.exti_cr_x(EXTIx_Px::PE, 10);
+        assert_eq!(mock::peek(AFIO_BASE + 0x08), 0x0001);
+        assert_eq!(mock::peek(AFIO_BASE + 0x0C), 0x3000);
+        assert_eq!(mock::peek(AFIO_BASE + 0x10), 0x0400);
+        assert_eq!(mock::peek(AFIO_BASE + 0x14), 0);
+    }
+}
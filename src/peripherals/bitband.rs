@@ -0,0 +1,129 @@
+//! ## bitband - Cortex-M3 bit-band alias
+//! peripheral (0x4000_0000..0x400F_FFFF) 과 SRAM (0x2000_0000..0x200F_FFFF) 의 각 비트는 <br/>
+//! alias 영역의 32-bit word 하나에 대응한다. alias word 에 1 / 0 을 쓰면 버스가 해당 비트만 set / clear 하므로 <br/>
+//! read-modify-write 사이에 인터럽트가 같은 레지스터를 바꿔도 값을 덮어쓰지 않는다. <br/>
+//! alias = alias_base + (addr - region_base) x 32 + bit x 4
+//!
+//! 주의: write-1-to-clear / read-to-clear 비트 (EXTI_PR, I2C SR1 등) 는 bit-band 도 내부적으로 <br/>
+//! read-modify-write 이므로 다른 pending 비트까지 클리어할 수 있다. 이런 레지스터는 단일 word 쓰기를 사용한다.
+
+use crate::peripherals::register::Reg;
+
+pub const PERIPH_BASE: u32 = 0x4000_0000;
+pub const PERIPH_BB_BASE: u32 = 0x4200_0000;
+pub const SRAM_BASE: u32 = 0x2000_0000;
+pub const SRAM_BB_BASE: u32 = 0x2200_0000;
+
+/// bit-band 영역 크기 (1 MB)
+const REGION_SIZE: u32 = 0x0010_0000;
+
+/// ## alias
+/// `addr` 의 `bit` 에 해당하는 alias 주소. bit-band 영역 밖이면 panic (const 문맥에서는 컴파일 에러).
+pub const fn alias(addr: u32, bit: u8) -> u32 {
+    assert!(bit < 32, "bit-band bit must be 0..=31");
+    let word = addr & !0b11;
+    let bit = bit as u32 + (addr & 0b11) * 8;
+    if word >= PERIPH_BASE && word < PERIPH_BASE + REGION_SIZE {
+        PERIPH_BB_BASE + (word - PERIPH_BASE) * 32 + bit * 4
+    } else if word >= SRAM_BASE && word < SRAM_BASE + REGION_SIZE {
+        SRAM_BB_BASE + (word - SRAM_BASE) * 32 + bit * 4
+    } else {
+        panic!("address is outside the bit-band regions")
+    }
+}
+
+/// ## target
+/// alias 주소 → (word 주소, bit). alias 영역이 아니면 `None`. `alias` 의 역변환.
+pub const fn target(alias: u32) -> Option<(u32, u8)> {
+    let (alias_base, base) = if alias >= PERIPH_BB_BASE && alias < PERIPH_BB_BASE + REGION_SIZE * 32 {
+        (PERIPH_BB_BASE, PERIPH_BASE)
+    } else if alias >= SRAM_BB_BASE && alias < SRAM_BB_BASE + REGION_SIZE * 32 {
+        (SRAM_BB_BASE, SRAM_BASE)
+    } else {
+        return None;
+    };
+    let offset = (alias - alias_base) / 4;
+    Some((base + (offset / 32) * 4, (offset % 32) as u8))
+}
+
+/// 단일 비트 alias. `Reg::bit_band` 로 만든다.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BitBand {
+    alias: Reg,
+}
+
+impl BitBand {
+    pub const fn new(addr: u32, bit: u8) -> BitBand {
+        BitBand {
+            alias: Reg::new(alias(addr, bit)),
+        }
+    }
+
+    pub const fn addr(&self) -> u32 {
+        self.alias.addr()
+    }
+
+    /// 원자적 set (true) / clear (false)
+    pub fn write(&self, value: bool) {
+        self.alias.write(value as u32);
+    }
+
+    pub fn set(&self) {
+        self.write(true);
+    }
+
+    pub fn clear(&self) {
+        self.write(false);
+    }
+
+    pub fn read(&self) -> bool {
+        self.alias.read() & 1 != 0
+    }
+}
+
+impl Reg {
+    /// ## bit_band
+    /// 이 레지스터의 `bit` 에 대한 bit-band alias. const 문맥에서 쓰면 주소 계산이 컴파일 시간에 끝난다.
+    /// ```ignore
+    /// const EXTI_IMR13: BitBand = Reg::new(EXTI_BASE).bit_band(13);
+    /// EXTI_IMR13.set();
+    /// ```
+    pub const fn bit_band(&self, bit: u8) -> BitBand {
+        BitBand::new(self.addr(), bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+    // RM0008 / PM0056 예시: RCC_CR (0x4002_1000) bit 0 HSION
+    const RCC_CR_HSION: BitBand = Reg::new(0x4002_1000).bit_band(0);
+
+    #[test]
+    fn alias_matches_reference_manual() {
+        assert_eq!(RCC_CR_HSION.addr(), 0x4242_0000);
+        assert_eq!(alias(0x2000_0300, 2), 0x2200_6008);
+        assert_eq!(alias(0x4001_0C0C, 5), 0x4221_8194);
+        assert_eq!(target(0x4221_8194), Some((0x4001_0C0C, 5)));
+        assert_eq!(target(0x4002_1000), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn alias_rejects_non_bit_band_address() {
+        alias(0xE000_E100, 0); // NVIC (PPB) 는 bit-band 영역이 아님
+    }
+
+    #[test]
+    fn bit_band_write_touches_only_its_bit() {
+        mock::reset();
+        mock::poke(0x4002_1000, 0xF0);
+        RCC_CR_HSION.set();
+        assert_eq!(mock::peek(0x4002_1000), 0xF1);
+        assert!(RCC_CR_HSION.read());
+        Reg::new(0x4002_1000).bit_band(4).clear();
+        assert_eq!(mock::peek(0x4002_1000), 0xE1);
+    }
+}
@@ -0,0 +1,91 @@
+//! ## dwt - DWT cycle counter
+//! CYCCNT 는 HCLK 마다 1 씩 증가하는 32-bit free-running counter. <br/>
+//! SysTick 처럼 소유권이 필요 없으므로 드라이버의 timeout 측정에 사용한다 (72 MHz 에서 약 59 초마다 wrap). <br/>
+//! 읽기만 하는 드라이버는 `wrapping_sub` 으로 경과 cycle 을 구한다.
+
+use crate::peripherals::register::Reg;
+
+pub const DWT_BASE: u32 = 0xE000_1000;
+/// Debug Exception and Monitor Control Register
+pub const DCB_DEMCR: u32 = 0xE000_EDFC;
+
+const DWT_CTRL: Reg = Reg::at(DWT_BASE, 0x00);
+const DWT_CYCCNT: Reg = Reg::at(DWT_BASE, 0x04);
+
+/// ## enable_cycle_counter
+/// DEMCR TRCENA (bit 24) 로 DWT 를 켠 뒤 CTRL CYCCNTENA (bit 0). 이미 켜져 있으면 쓰지 않는다. <br/>
+/// `Deadline` 이 만들어질 때마다 호출하므로 드라이버가 따로 부를 필요는 없다.
+pub fn enable_cycle_counter() {
+    let demcr = Reg::new(DCB_DEMCR);
+    if !demcr.read_bit(24) {
+        demcr.set_bits(1 << 24);
+    }
+    if !DWT_CTRL.read_bit(0) {
+        DWT_CTRL.set_bits(1);
+    }
+}
+
+pub fn cycle_count() -> u32 {
+    DWT_CYCCNT.read()
+}
+
+/// ## Deadline
+/// 시작 시점의 CYCCNT 와 허용 cycle 수. `expired` 를 polling 한다. <br/>
+/// CYCCNT 가 멈춰 있으면 영원히 expire 되지 않으므로 만들 때 counter 를 켠다.
+#[derive(Clone, Copy)]
+pub struct Deadline {
+    start: u32,
+    cycles: u32,
+}
+
+impl Deadline {
+    pub fn after_cycles(cycles: u32) -> Deadline {
+        enable_cycle_counter();
+        Deadline {
+            start: cycle_count(),
+            cycles,
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        cycle_count().wrapping_sub(self.start) > self.cycles
+    }
+}
+
+/// `us` 마이크로초 → HCLK cycle (u32 범위로 포화)
+pub const fn us_to_cycles(hclk: u32, us: u32) -> u32 {
+    let cycles = hclk as u64 * us as u64 / 1_000_000;
+    if cycles > u32::MAX as u64 {
+        u32::MAX
+    } else {
+        cycles as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+    #[test]
+    fn deadline_survives_counter_wrap() {
+        mock::reset();
+        mock::poke(DWT_BASE + 0x04, u32::MAX - 10);
+        let deadline = Deadline::after_cycles(100);
+        mock::poke(DWT_BASE + 0x04, 50); // wrap 후 61 cycle
+        assert!(!deadline.expired());
+        mock::poke(DWT_BASE + 0x04, 90);
+        assert!(deadline.expired());
+        assert_eq!(us_to_cycles(72_000_000, 25_000), 1_800_000);
+    }
+
+    #[test]
+    fn deadline_starts_the_cycle_counter() {
+        mock::reset();
+        Deadline::after_cycles(100);
+        assert_eq!(mock::peek(DCB_DEMCR), 1 << 24); // TRCENA
+        assert_eq!(mock::peek(DWT_BASE), 1); // CYCCNTENA
+        Deadline::after_cycles(100);
+        assert_eq!(mock::writes(DWT_BASE).len(), 1); // 이미 켜져 있으면 다시 쓰지 않음
+    }
+}
@@ -1,90 +1,417 @@
 #![allow(non_snake_case)]
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::peripherals::{
+    afio::{EXTIx_Px, AFIO},
+    gpio::{Input, Pin},
+    interrupt::Interrupt,
+    nvic::NVIC,
+    register::Reg,
+};
+
+pub const EXTI_BASE: u32 = 0x4001_0400;
+
+/// GPIO 에 연결되는 EXTI line 수 (line 16..18 은 PVD / RTC alarm / USB wakeup)
+pub const EXTI_GPIO_LINES: usize = 16;
+
+type Callback = Cell<Option<fn()>>;
+
+/// ## Edge - EXTI trigger
+/// - **Rising**: RTSR <br/>
+/// - **Falling**: FTSR <br/>
+/// - **Both**: RTSR + FTSR <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Both,
+}
+
+/// ## line_interrupt
+/// EXTI line 0..=15 이 연결된 NVIC IRQ. line 5..9 와 10..15 는 IRQ 하나를 공유한다.
+pub const fn line_interrupt(line: u8) -> Interrupt {
+    match line {
+        0 => Interrupt::EXTI0,
+        1 => Interrupt::EXTI1,
+        2 => Interrupt::EXTI2,
+        3 => Interrupt::EXTI3,
+        4 => Interrupt::EXTI4,
+        5..=9 => Interrupt::EXTI9_5,
+        10..=15 => Interrupt::EXTI15_10,
+        _ => panic!("EXTI GPIO line must be 0..=15"),
+    }
+}
+
+/// 같은 IRQ 를 쓰는 line 들의 mask (ex. line 7 → line 5..9)
+const fn shared_lines_mask(line: u8) -> u32 {
+    match line {
+        5..=9 => 0b11111 << 5,
+        10..=15 => 0b111111 << 10,
+        _ => 1 << line,
+    }
+}
+
+/// line 별 콜백. 인터럽트 핸들러가 pending bit 를 클리어한 뒤 호출한다.
+#[cfg(not(test))]
+static CALLBACKS: Mutex<[Callback; EXTI_GPIO_LINES]> =
+    Mutex::new([const { Cell::new(None) }; EXTI_GPIO_LINES]);
+
+#[cfg(not(test))]
+fn with_callbacks<R>(f: impl FnOnce(&[Callback; EXTI_GPIO_LINES]) -> R) -> R {
+    critical_section::with(|cs| f(CALLBACKS.borrow(cs)))
+}
+
+/// 호스트 테스트용 콜백 테이블 (thread 별). 병렬로 실행되는 테스트끼리 콜백을 공유하지 않는다.
+#[cfg(test)]
+fn with_callbacks<R>(f: impl FnOnce(&[Callback; EXTI_GPIO_LINES]) -> R) -> R {
+    std::thread_local! {
+        static CALLBACKS: [Callback; EXTI_GPIO_LINES] = [const { Cell::new(None) }; EXTI_GPIO_LINES];
+    }
+    CALLBACKS.with(f)
+}
+
 pub struct exti {
     base: u32,
-    imr:    *mut u32,
-    emr:    *mut u32,
-    rtsr:   *mut u32,
-    ftsr:   *mut u32,
-    swier:  *mut u32,
-    pr:     *mut u32,
+    imr:    Reg,
+    emr:    Reg,
+    rtsr:   Reg,
+    ftsr:   Reg,
+    swier:  Reg,
+    pr:     Reg,
 }
 
 
 impl exti {
-    pub fn new(base: u32) -> exti {
+    pub(super) fn new(base: u32) -> exti {
         exti {
             base,
-            imr:    (base + 0x00) as *mut u32,
-            emr:    (base + 0x04) as *mut u32,
-            rtsr:   (base + 0x08) as *mut u32,
-            ftsr:   (base + 0x0C) as *mut u32,
-            swier:  (base + 0x10) as *mut u32,
-            pr:     (base + 0x14) as *mut u32,
+            imr:    Reg::at(base, 0x00),
+            emr:    Reg::at(base, 0x04),
+            rtsr:   Reg::at(base, 0x08),
+            ftsr:   Reg::at(base, 0x0C),
+            swier:  Reg::at(base, 0x10),
+            pr:     Reg::at(base, 0x14),
         }
     }
 
+    /// IMR / RTSR / FTSR 는 main 과 핸들러가 모두 바꿀 수 있으므로 bit-band 로 한 비트만 쓴다
     pub fn imr_set(&self, MRx: u8, enable: bool) {
-        unsafe {
-            let mut imr_val = self.imr.read_volatile();
-            if enable {
-                imr_val |= 1 << MRx;
-            } else {
-                imr_val &= !(1 << MRx);
-            }
-            self.imr.write_volatile(imr_val);
-        }
-    }
-    pub fn rstr_set(&self, TRx: u8, val: bool) {
-        unsafe {
-            let mut rtsr_val = self.rtsr.read_volatile();
-            if val {
-                rtsr_val |= 1 << TRx;
-            } else {
-                rtsr_val &= !(1 << TRx);
-            }
-            self.rtsr.write_volatile(rtsr_val);
-        }
+        self.imr.bit_band(MRx).write(enable);
     }
     pub fn rtsr_set(&self, TRx: u8, val: bool) {
-        unsafe {
-            let mut rtsr_val = self.rtsr.read_volatile();
-            if val {
-                rtsr_val |= 1 << TRx;
-            } else {
-                rtsr_val &= !(1 << TRx);
-            }
-            self.rtsr.write_volatile(rtsr_val);
-        }
+        self.rtsr.bit_band(TRx).write(val);
     }
 
 
     pub fn ftsr_set(&self, TRx: u8, val: bool) {
-        unsafe {
-            let mut ftsr_val = self.ftsr.read_volatile();
-            if val {
-                ftsr_val |= 1 << TRx;
-            } else {
-                ftsr_val &= !(1 << TRx);
-            }
-            self.ftsr.write_volatile(ftsr_val);
+        self.ftsr.bit_band(TRx).write(val);
+    }
+    /// ## listen - GPIO 핀을 EXTI 인터럽트 소스로 설정
+    /// 1. AFIO EXTICRx 로 line N 을 핀의 포트에 연결 <br/>
+    /// 2. `edge` 에 맞게 RTSR / FTSR 설정 <br/>
+    /// 3. 이전에 남아 있던 pending 클리어 후 IMR unmask <br/>
+    /// 4. line 에 해당하는 NVIC IRQ enable <br/>
+    ///
+    /// AFIO clock 은 미리 켜 두어야 한다 (`rcc.enable_clock(Peripheral::AFIO)`).
+    /// ```ignore
+    /// let button = gpioc.p13.into_pull_up_input();
+    /// exti.on_line(13, on_button);
+    /// exti.listen(&button, Edge::Falling, &afio, &nvic);
+    /// ```
+    pub fn listen<const P: char, const N: u8, PULL>(
+        &self,
+        _pin: &Pin<P, N, Input<PULL>>,
+        edge: Edge,
+        afio: &AFIO,
+        nvic: &NVIC,
+    ) {
+        afio.exti_cr_x(EXTIx_Px::from_port(P), N);
+        self.rtsr_set(N, matches!(edge, Edge::Rising | Edge::Both));
+        self.ftsr_set(N, matches!(edge, Edge::Falling | Edge::Both));
+        self.pr.write(1 << N);
+        self.imr_set(N, true);
+        nvic.enable(line_interrupt(N));
+    }
+
+    /// ## unlisten - `listen` / `listen_event` 되돌리기
+    /// mask, trigger, pending 을 지우고 AFIO 연결을 reset 값 (PA) 으로 되돌린다. <br/>
+    /// NVIC IRQ 는 같은 IRQ 를 공유하는 다른 line (EXTI9_5, EXTI15_10) 이 모두 mask 되었을 때만 disable.
+    pub fn unlisten<const P: char, const N: u8, PULL>(
+        &self,
+        _pin: &Pin<P, N, Input<PULL>>,
+        afio: &AFIO,
+        nvic: &NVIC,
+    ) {
+        self.imr_set(N, false);
+        self.emr_set(N, false);
+        self.rtsr_set(N, false);
+        self.ftsr_set(N, false);
+        self.pr.write(1 << N);
+        afio.exti_cr_x(EXTIx_Px::PA, N);
+        if self.imr.read() & shared_lines_mask(N) == 0 {
+            nvic.disable(line_interrupt(N));
         }
     }
+
+    /// ## on_line - line 별 콜백 등록
+    /// EXTI0..EXTI4, EXTI9_5, EXTI15_10 핸들러가 pending bit 를 클리어한 뒤 `callback` 을 호출한다. <br/>
+    /// 같은 IRQ 를 공유하는 line (ex. PA5 버튼과 PB8 센서) 도 각자의 콜백으로 전달된다. <br/>
+    /// 콜백은 인터럽트 문맥에서 실행되므로 짧게 유지한다. `line` 이 0..=15 가 아니면 panic.
+    pub fn on_line(&self, line: u8, callback: fn()) {
+        assert!((line as usize) < EXTI_GPIO_LINES, "EXTI GPIO line must be 0..=15");
+        with_callbacks(|callbacks| callbacks[line as usize].set(Some(callback)));
+    }
+
+    pub fn remove_callback(&self, line: u8) {
+        assert!((line as usize) < EXTI_GPIO_LINES, "EXTI GPIO line must be 0..=15");
+        with_callbacks(|callbacks| callbacks[line as usize].set(None));
+    }
+
+    /// EMR - Event mask. 이벤트는 ISR 없이 WFE 로 잠든 코어를 깨운다 (pending bit 도 세우지 않는다).
+    pub fn emr_set(&self, MRx: u8, enable: bool) {
+        self.emr.bit_band(MRx).write(enable);
+    }
+
+    /// ## listen_event - GPIO 핀을 EXTI 이벤트 소스로 설정
+    /// `listen` 과 같이 AFIO / trigger 를 설정하지만 IMR 과 NVIC 대신 EMR 만 unmask 한다. <br/>
+    /// ```ignore
+    /// exti.listen_event(&button, Edge::Falling, &afio);
+    /// cortex_m::asm::wfe(); // 버튼을 누를 때까지 대기
+    /// ```
+    pub fn listen_event<const P: char, const N: u8, PULL>(
+        &self,
+        _pin: &Pin<P, N, Input<PULL>>,
+        edge: Edge,
+        afio: &AFIO,
+    ) {
+        afio.exti_cr_x(EXTIx_Px::from_port(P), N);
+        self.rtsr_set(N, matches!(edge, Edge::Rising | Edge::Both));
+        self.ftsr_set(N, matches!(edge, Edge::Falling | Edge::Both));
+        self.emr_set(N, true);
+    }
+
+    /// ## swier_trigger - Software interrupt
+    /// SWIER 에 1 을 쓰면 IMR / EMR 이 unmask 된 line 에 인터럽트 / 이벤트가 발생한다. <br/>
+    /// SWIER 비트는 PR 의 해당 비트를 클리어할 때 함께 클리어된다 (`dispatch`, `pr_clear`).
+    pub fn swier_trigger(&self, SWIERx: u8) {
+        self.swier.bit_band(SWIERx).set();
+    }
+
     /// EXTI_PR Pending register
     pub fn pr_read(&self, PRx: u8) -> bool {
-        unsafe {
-            let pr_val = self.pr.read_volatile();
-            return pr_val & (1 << PRx) != 0
+        self.pr.read_bit(PRx)
+    }
 
+    /// EXTI_PR Pending register <br/>
+    /// write-1-to-clear 이므로 read-modify-write 나 bit-band 를 쓰지 않고 해당 비트만 쓴다. <br/>
+    /// (RMW 는 읽은 순간 pending 이던 다른 line 까지 클리어한다)
+    pub fn pr_clear(&self, PRx: u8) {
+        self.pr.write(1 << PRx);
+    }
+
+    /// ## pr_take
+    /// `mask` 중 pending 인 line 들을 한 번의 쓰기로 클리어하고 클리어한 비트를 돌려준다. <br/>
+    /// mask 밖의 pending 은 그대로 남는다.
+    pub fn pr_take(&self, mask: u32) -> u32 {
+        let pending = self.pr.read() & mask;
+        if pending != 0 {
+            self.pr.write(pending);
         }
+        pending
     }
-    
-    /// EXTI_PR Pending register
-    pub fn pr_clear(&self, PRx: u8) {
-        unsafe {
-            let mut pr_val = self.pr.read_volatile();
-            pr_val |= (1 << PRx);
-            self.pr.write_volatile(pr_val);
+
+}
+
+/// ## dispatch
+/// `first..=last` line 중 pending 인 것을 하나씩 클리어하고 등록된 콜백을 호출한다. <br/>
+/// PR 은 write-1-to-clear 이고 해당 비트만 쓰므로 main 이 가진 `exti` 와 경쟁하지 않는다. <br/>
+/// 콜백이 없는 line 도 pending 은 클리어한다 (핸들러 재진입 방지).
+fn dispatch(first: u8, last: u8) {
+    let pr = Reg::at(EXTI_BASE, 0x14);
+    let pending = pr.read();
+    for line in first..=last {
+        if pending & (1 << line) != 0 {
+            pr.write(1 << line);
+            let callback = with_callbacks(|callbacks| callbacks[line as usize].get());
+            if let Some(callback) = callback {
+                callback();
+            }
         }
     }
+}
+
+#[cfg(not(test))]
+mod handlers {
+    use cortex_m_rt::interrupt;
+
+    use super::dispatch;
+    use crate::peripherals::interrupt::interrupt;
+
+    #[interrupt]
+    fn EXTI0() {
+        dispatch(0, 0);
+    }
+
+    #[interrupt]
+    fn EXTI1() {
+        dispatch(1, 1);
+    }
+
+    #[interrupt]
+    fn EXTI2() {
+        dispatch(2, 2);
+    }
+
+    #[interrupt]
+    fn EXTI3() {
+        dispatch(3, 3);
+    }
+
+    #[interrupt]
+    fn EXTI4() {
+        dispatch(4, 4);
+    }
+
+    #[interrupt]
+    fn EXTI9_5() {
+        dispatch(5, 9);
+    }
+
+    #[interrupt]
+    fn EXTI15_10() {
+        dispatch(10, 15);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
 
-}
\ No newline at end of file
+
+    #[test]
+    fn trigger_and_mask_bits() {
+        mock::reset();
+        let exti = exti::new(EXTI_BASE);
+        exti.imr_set(13, true);
+        exti.ftsr_set(13, true);
+        exti.rtsr_set(2, true);
+        exti.rtsr_set(2, false);
+        assert_eq!(mock::peek(EXTI_BASE + 0x00), 1 << 13);
+        assert_eq!(mock::peek(EXTI_BASE + 0x0C), 1 << 13);
+        assert_eq!(mock::peek(EXTI_BASE + 0x08), 0);
+    }
+
+    #[test]
+    fn pr_read_single_line() {
+        mock::reset();
+        mock::poke(EXTI_BASE + 0x14, 1 << 13);
+        let exti = exti::new(EXTI_BASE);
+        assert!(exti.pr_read(13));
+        assert!(!exti.pr_read(12));
+    }
+
+    #[test]
+    fn dispatch_clears_each_pending_line_and_calls_its_callback() {
+        use std::cell::RefCell;
+        std::thread_local! {
+            static CALLED: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+        }
+        mock::reset();
+        let exti = exti::new(EXTI_BASE);
+        exti.on_line(13, || CALLED.with(|c| c.borrow_mut().push(13)));
+        exti.on_line(10, || CALLED.with(|c| c.borrow_mut().push(10)));
+        // 13, 11 (콜백 없음), 3 (다른 IRQ) 이 pending
+        mock::poke(EXTI_BASE + 0x14, (1 << 13) | (1 << 11) | (1 << 3));
+        dispatch(10, 15);
+        assert_eq!(CALLED.with(|c| c.borrow().clone()), [13]);
+        assert_eq!(mock::writes(EXTI_BASE + 0x14), [1 << 11, 1 << 13]);
+        exti.remove_callback(13);
+        mock::poke(EXTI_BASE + 0x14, 1 << 13);
+        dispatch(10, 15);
+        assert_eq!(CALLED.with(|c| c.borrow().len()), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "EXTI GPIO line must be 0..=15")]
+    fn on_line_rejects_non_gpio_line() {
+        exti::new(EXTI_BASE).on_line(16, || ());
+    }
+
+    #[test]
+    fn listen_routes_pin_and_enables_shared_irq() {
+        use crate::peripherals::{afio::AFIO_BASE, gpio::Parts, nvic::NVIC_BASE};
+        mock::reset();
+        let exti = exti::new(EXTI_BASE);
+        let afio = AFIO::new(AFIO_BASE);
+        let nvic = NVIC::new(NVIC_BASE);
+        let gpioc = Parts::<'C'>::new();
+        let gpiob = Parts::<'B'>::new();
+        let button = gpioc.p13.into_pull_up_input();
+        let sensor = gpiob.p10.into_floating_input();
+
+        exti.listen(&button, Edge::Falling, &afio, &nvic);
+        exti.listen(&sensor, Edge::Both, &afio, &nvic);
+        assert_eq!(mock::peek(AFIO_BASE + 0x14) & 0xF0, 0b0010 << 4); // EXTICR4 EXTI13 = PC
+        assert_eq!(mock::peek(AFIO_BASE + 0x10) & 0xF00, 0b0001 << 8); // EXTICR3 EXTI10 = PB
+        assert_eq!(mock::peek(EXTI_BASE + 0x08), 1 << 10); // RTSR
+        assert_eq!(mock::peek(EXTI_BASE + 0x0C), (1 << 13) | (1 << 10)); // FTSR
+        assert_eq!(mock::peek(EXTI_BASE), (1 << 13) | (1 << 10)); // IMR
+        assert_eq!(mock::peek(NVIC_BASE + 0x04), 1 << (40 - 32)); // ISER1 EXTI15_10
+
+        // line 10 이 아직 EXTI15_10 을 쓰므로 NVIC 는 그대로
+        exti.unlisten(&button, &afio, &nvic);
+        assert_eq!(mock::peek(EXTI_BASE), 1 << 10);
+        assert_eq!(mock::peek(EXTI_BASE + 0x0C), 1 << 10);
+        assert_eq!(mock::peek(AFIO_BASE + 0x14) & 0xF0, 0);
+        assert!(mock::writes(NVIC_BASE + 0x84).is_empty());
+
+        exti.unlisten(&sensor, &afio, &nvic);
+        assert_eq!(mock::writes(NVIC_BASE + 0x84), [1 << (40 - 32)]); // ICER1
+    }
+
+    #[test]
+    fn line_interrupt_groups() {
+        assert_eq!(line_interrupt(4), Interrupt::EXTI4);
+        assert_eq!(line_interrupt(7), Interrupt::EXTI9_5);
+        assert_eq!(line_interrupt(15), Interrupt::EXTI15_10);
+    }
+
+    #[test]
+    fn pr_clear_writes_only_target_bit() {
+        mock::reset();
+        mock::poke(EXTI_BASE + 0x14, (1 << 13) | (1 << 2));
+        let exti = exti::new(EXTI_BASE);
+        exti.pr_clear(13);
+        assert_eq!(mock::writes(EXTI_BASE + 0x14), [1 << 13]);
+    }
+
+    #[test]
+    fn pr_take_clears_only_masked_pending_lines() {
+        mock::reset();
+        mock::poke(EXTI_BASE + 0x14, (1 << 13) | (1 << 11) | (1 << 2));
+        let exti = exti::new(EXTI_BASE);
+        assert_eq!(exti.pr_take(0xFC00), (1 << 13) | (1 << 11));
+        assert_eq!(mock::writes(EXTI_BASE + 0x14), [(1 << 13) | (1 << 11)]);
+        assert_eq!(exti.pr_take(1 << 5), 0);
+        assert_eq!(mock::writes(EXTI_BASE + 0x14).len(), 1);
+    }
+
+    #[test]
+    fn event_mode_and_software_trigger() {
+        use crate::peripherals::{afio::AFIO_BASE, gpio::Parts};
+        mock::reset();
+        let exti = exti::new(EXTI_BASE);
+        let afio = AFIO::new(AFIO_BASE);
+        let pa0 = Parts::<'A'>::new().p0.into_pull_down_input();
+        exti.listen_event(&pa0, Edge::Rising, &afio);
+        assert_eq!(mock::peek(EXTI_BASE + 0x04), 1); // EMR
+        assert_eq!(mock::peek(EXTI_BASE), 0); // IMR 는 그대로
+        assert_eq!(mock::peek(EXTI_BASE + 0x08), 1); // RTSR
+        exti.swier_trigger(3);
+        exti.swier_trigger(0);
+        assert_eq!(mock::peek(EXTI_BASE + 0x10), 0b1001);
+    }
+}
@@ -1,7 +1,10 @@
+use crate::peripherals::register::Reg;
+
 pub struct FLASH {
     base: u32,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum FLASH_LATENCY {
     _0WS = 0b000,
     _1WS = 0b001,
@@ -9,35 +12,19 @@ pub enum FLASH_LATENCY {
 }
 
 impl FLASH {
-    pub fn new(base: u32) -> FLASH {
+    pub(super) fn new(base: u32) -> FLASH {
         FLASH { base }
     }
 
-    fn ACR(&self) -> *mut u32 {
-        (self.base + 0x00) as *mut u32
+    fn ACR(&self) -> Reg {
+        Reg::at(self.base, 0x00)
     }
 
     pub fn ACR_PRFTBE(&self, enable: bool) {
-        unsafe {
-            let flash_acr = self.ACR();
-            let mut flash_acr_val = flash_acr.read_volatile();
-            if enable {
-                flash_acr_val |= (1 << 4); // Enable prefetch buffer
-            } else {
-                flash_acr_val &= !(1 << 4); // Disable prefetch buffer
-            }
-            flash_acr.write_volatile(flash_acr_val);
-        }
+        self.ACR().set_bit(4, enable); // Enable / Disable prefetch buffer
     }
 
     pub fn ACR_LATENCY(&self, flash_latency: FLASH_LATENCY) {
-        unsafe {
-            let flash_acr = self.ACR();
-            let latency = flash_latency as u32;
-            let mut flash_acr_val = flash_acr.read_volatile();
-            flash_acr_val &= !(0b111 << 0);
-            flash_acr_val |= (latency << 0);
-            flash_acr.write_volatile(flash_acr_val);
-        }
+        self.ACR().write_field(0, 0b111, flash_latency as u32);
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,15 @@
 #![allow(non_snake_case)]
+use core::marker::PhantomData;
+
+use crate::peripherals::register::Reg;
+
 pub enum GpioXBase {
     A = 0x4001_0800,
     B = 0x4001_0C00,
     C = 0x4001_1000,
+    /// Nucleo-F103RB: PD2 (morpho CN7), PD0/PD1 (OSC_IN/OSC_OUT)
+    D = 0x4001_1400,
+    E = 0x4001_1800,
 }
 
 pub struct Gpio {
@@ -10,39 +17,102 @@ pub struct Gpio {
 }
 
 impl Gpio {
-    pub fn new(base: GpioXBase) -> Self {
+    pub(super) fn new(base: GpioXBase) -> Self {
         Gpio { base: base as u32 }
     }
 
-    unsafe fn CRL(&self) -> *mut u32 {
-        (self.base + 0x00) as *mut u32
+    fn CRL(&self) -> Reg {
+        Reg::at(self.base, 0x00)
+    }
+
+    fn CRH(&self) -> Reg {
+        Reg::at(self.base, 0x04)
+    }
+
+    fn IDR(&self) -> Reg {
+        Reg::at(self.base, 0x08)
     }
 
-    unsafe fn CRH(&self) -> *mut u32 {
-        (self.base + 0x04) as *mut u32
+    fn ODR(&self) -> Reg {
+        Reg::at(self.base, 0x0C)
     }
 
-    unsafe fn IDR(&self) -> *mut u32 {
-        (self.base + 0x08) as *mut u32
+    fn BSRR(&self) -> Reg {
+        Reg::at(self.base, 0x10)
     }
 
-    unsafe fn BSRR(&self) -> *mut u32 {
-        (self.base + 0x10) as *mut u32
+    fn BRR(&self) -> Reg {
+        Reg::at(self.base, 0x14)
+    }
+
+    fn LCKR(&self) -> Reg {
+        Reg::at(self.base, 0x18)
     }
 
     pub fn bsrr_write(&self, port: u8) {
-        unsafe {
-            self.BSRR().write_volatile(1 << port);
-        }
+        self.BSRR().write(1 << port);
     }
 
     pub fn bsrr_reset(&self, port: u8) {
-        unsafe {
-            self.BSRR().write_volatile(1 << (port + 16));
-        }
+        self.BSRR().write(1 << (port + 16));
     }
     pub fn idr_read(&self, port: u8) -> u32 {
-        unsafe { (self.IDR().read_volatile() & (1 << port)) >> port }
+        self.IDR().read_bit(port) as u32
+    }
+
+    /// IDR 전체 (pin 0..15)
+    pub fn idr_read_port(&self) -> u16 {
+        self.IDR().read() as u16
+    }
+
+    /// ODR read-back: 출력으로 설정한 값 (핀의 실제 레벨은 `idr_read`)
+    pub fn odr_read(&self, port: u8) -> u32 {
+        self.ODR().read_bit(port) as u32
+    }
+
+    pub fn odr_read_port(&self) -> u16 {
+        self.ODR().read() as u16
+    }
+
+    /// BRR - Port bit reset (BSRR 의 reset 절반과 같은 동작)
+    pub fn brr_write(&self, port: u8) {
+        self.BRR().write(1 << port);
+    }
+
+    /// ODR 을 읽어 반대 값을 BSRR 로 한 번에 기록
+    pub fn toggle(&self, port: u8) {
+        if self.ODR().read_bit(port) {
+            self.bsrr_reset(port);
+        } else {
+            self.bsrr_write(port);
+        }
+    }
+
+    /// ## bsrr_write_masked
+    /// `mask` 에 해당하는 핀만 `value` 로 설정한다. set / reset 을 BSRR 한 번의 store 로 기록하므로 <br/>
+    /// 다른 핀이나 인터럽트와 read-modify-write 경쟁이 없다.
+    pub fn bsrr_write_masked(&self, mask: u16, value: u16) {
+        let set = (mask & value) as u32;
+        let reset = (mask & !value) as u32;
+        self.BSRR().write(set | (reset << 16));
+    }
+
+    /// ## lckr_lock - Port configuration lock
+    /// `mask` 핀의 CRL/CRH 설정을 다음 reset 까지 고정한다. <br/>
+    /// lock key sequence: LCKK=1 → LCKK=0 → LCKK=1 쓰기 (LCKy 값은 동일), LCKR 두 번 읽기. <br/>
+    /// 두 번째 읽기에서 LCKK=1 이면 잠김 (`true`).
+    pub fn lckr_lock(&self, mask: u16) -> bool {
+        let lckk = 1 << 16;
+        let pins = mask as u32;
+        self.LCKR().write(lckk | pins);
+        self.LCKR().write(pins);
+        self.LCKR().write(lckk | pins);
+        let _ = self.LCKR().read();
+        self.lckr_is_locked()
+    }
+
+    pub fn lckr_is_locked(&self) -> bool {
+        self.LCKR().read_bit(16)
     }
 
     
@@ -58,42 +128,525 @@ impl Gpio {
     /// **01**: Output mode, max speed 10 MHz.              <br/>
     /// **10**: Output mode, max speed 2 MHz.               <br/>
     /// **11**: Output mode, max speed 50 MHz.              <br/>
-    pub fn crl_port_config(&self, port: u8, cnf_mode: u32) {
+    fn crl_port_config(&self, port: u8, cnf_mode: u32) {
         assert!(port < 8, "Port number must be between 0 and 7");
-        unsafe {
-            let shift = port * 4;
-            let mut crl_val = self.CRL().read_volatile();
-            crl_val &= !(0b1111 << shift); // Clear the current configuration
-            crl_val |= (cnf_mode << shift); // Set the configuration
-            self.CRL().write_volatile(crl_val);
-        }
+        let shift = port * 4;
+        self.CRL().write_field(shift, 0b1111, cnf_mode); // Clear and set the configuration
     }
 
     
 
-    pub fn crh_port_config(&self, port: u8, mode: u32) {
+    fn crh_port_config(&self, port: u8, mode: u32) {
         assert!(
             port >= 8 && port < 16,
             "Port number must be between 8 and 15 for CRH"
         );
-        unsafe {
-            let shift = (port - 8) * 4;
-            let crh_reg = self.CRH();
-
-            let mut cr_val = crh_reg.read_volatile();
-            cr_val &= !(0b1111 << shift); // Clear the current configuration
-            cr_val |= (mode << shift); // Set the mode
-            crh_reg.write_volatile(cr_val);
+        let shift = (port - 8) * 4;
+        self.CRH().write_field(shift, 0b1111, mode); // Clear and set the mode
+    }
+
+    /// 핀 번호에 따라 CRL / CRH 선택 <br/>
+    /// 모드 변경은 `Pin::into_*` 를 거친다. 타입 상태 밖에서 직접 쓰는 곳은 I2C bus recovery 뿐이다.
+    pub(super) fn port_config(&self, port: u8, cnf_mode: u32) {
+        if port < 8 {
+            self.crl_port_config(port, cnf_mode);
+        } else {
+            self.crh_port_config(port, cnf_mode);
+        }
+    }
+
+    /// 현재 CNFy + MODEy. 잠시 바꾼 설정을 되돌릴 때 사용
+    pub(super) fn read_port_config(&self, port: u8) -> u32 {
+        if port < 8 {
+            self.CRL().read_field(port * 4, 0b1111)
+        } else {
+            self.CRH().read_field((port - 8) * 4, 0b1111)
         }
     }
-    pub fn configure_pc2_as_analog(&self) {
-        unsafe {
-            let gpio_c_crl = 0x4001_1000 as *mut u32; // GPIOC_CRL 레지스터 주소
-            let mut moder = gpio_c_crl.read_volatile();
-            moder &= !(0b11 << (2 * 4));  // CNF2[1:0] = 00 (아날로그 모드)
-            moder &= !(0b11 << ((2 * 4) + 2));  // MODE2[1:0] = 00 (입력 모드)
-            gpio_c_crl.write_volatile(moder);
+
+    /// ## input_config
+    /// 입력 모드 (MODE=00) 설정. CNF=10 의 pull-up / pull-down 은 ODR 비트가 방향을 정하므로 <br/>
+    /// BSRR 로 해당 핀의 ODR 을 먼저 기록한 뒤 CNF/MODE 를 쓴다. 이전 ODR 값과 관계없이 결과가 같다. <br/>
+    /// `Pull::None` 은 floating input (CNF=01) 이고 ODR 은 건드리지 않는다.
+    fn input_config(&self, port: u8, pull: Pull) {
+        match pull {
+            Pull::Up => self.bsrr_write(port),
+            Pull::Down => self.bsrr_reset(port),
+            Pull::None => {}
         }
+        self.port_config(port, pull.cnf_mode());
+    }
+}
+
+/// ## Pull - input pull-up / pull-down
+/// - **Up**: CNF=10, ODR=1 <br/>
+/// - **Down**: CNF=10, ODR=0 <br/>
+/// - **None**: CNF=01 (floating) <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pull {
+    Up,
+    Down,
+    None,
+}
+
+impl Pull {
+    /// CNFy + MODEy (MODE=00 input)
+    const fn cnf_mode(self) -> u32 {
+        match self {
+            Pull::Up | Pull::Down => 0b1000,
+            Pull::None => 0b0100,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Type-state pins
+// ---------------------------------------------------------------------------
+
+/// Input mode (type state)
+pub struct Input<PULL> {
+    _pull: PhantomData<PULL>,
+}
+pub struct Floating;
+pub struct PullUp;
+pub struct PullDown;
+
+/// General purpose output mode (type state)
+pub struct Output<OTYPE> {
+    _otype: PhantomData<OTYPE>,
+}
+/// Alternate function output mode (type state)
+pub struct Alternate<OTYPE> {
+    _otype: PhantomData<OTYPE>,
+}
+pub struct PushPull;
+pub struct OpenDrain;
+
+/// Analog mode (type state, ADC 입력)
+pub struct Analog;
+
+/// ## Speed - output MODEy
+/// - **Mhz10**: 01 <br/>
+/// - **Mhz2**: 10 <br/>
+/// - **Mhz50**: 11 <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Speed {
+    Mhz10 = 0b01,
+    Mhz2 = 0b10,
+    Mhz50 = 0b11,
+}
+
+const fn port_base(port: char) -> u32 {
+    match port {
+        'A' => GpioXBase::A as u32,
+        'B' => GpioXBase::B as u32,
+        'C' => GpioXBase::C as u32,
+        'D' => GpioXBase::D as u32,
+        'E' => GpioXBase::E as u32,
+        _ => panic!("GPIO port must be 'A'..='E'"),
+    }
+}
+
+/// ## Pin - zero-sized type-state pin
+/// `P` 는 포트 ('A'..='E'), `N` 은 핀 번호, `MODE` 는 현재 설정. 잘못된 포트/핀 번호는 컴파일 에러. <br/>
+/// `into_*` 로 모드를 바꾸며, 모드에 맞지 않는 메서드 (ex. 입력 핀의 `set_high`) 는 컴파일되지 않는다.
+/// ```ignore
+/// let gpioa = Parts::<'A'>::new();
+/// let mut led: PA5<Output<PushPull>> = gpioa.p5.into_push_pull_output(Speed::Mhz2);
+/// led.set_high();
+/// ```
+pub struct Pin<const P: char, const N: u8, MODE> {
+    _mode: PhantomData<MODE>,
+}
+
+impl<const P: char, const N: u8, MODE> Pin<P, N, MODE> {
+    const BASE: u32 = port_base(P);
+    const VALID: () = assert!(N < 16, "GPIO pin number must be 0..=15");
+
+    const fn new() -> Self {
+        let () = Self::VALID;
+        let _ = Self::BASE;
+        Pin { _mode: PhantomData }
+    }
+
+    fn gpio(&self) -> Gpio {
+        Gpio { base: Self::BASE }
+    }
+
+    fn into_mode<NEW>(self, cnf_mode: u32) -> Pin<P, N, NEW> {
+        self.gpio().port_config(N, cnf_mode);
+        Pin::new()
+    }
+
+    fn into_input<PULL>(self, pull: Pull) -> Pin<P, N, Input<PULL>> {
+        self.gpio().input_config(N, pull);
+        Pin::new()
+    }
+
+    pub fn into_floating_input(self) -> Pin<P, N, Input<Floating>> {
+        self.into_input(Pull::None)
+    }
+
+    pub fn into_pull_up_input(self) -> Pin<P, N, Input<PullUp>> {
+        self.into_input(Pull::Up)
+    }
+
+    pub fn into_pull_down_input(self) -> Pin<P, N, Input<PullDown>> {
+        self.into_input(Pull::Down)
+    }
+
+    pub fn into_push_pull_output(self, speed: Speed) -> Pin<P, N, Output<PushPull>> {
+        self.into_mode(speed as u32) // CNF = 0b00
+    }
+
+    pub fn into_open_drain_output(self, speed: Speed) -> Pin<P, N, Output<OpenDrain>> {
+        self.into_mode((0b01 << 2) | speed as u32)
+    }
+
+    pub fn into_alternate_push_pull(self, speed: Speed) -> Pin<P, N, Alternate<PushPull>> {
+        self.into_mode((0b10 << 2) | speed as u32)
+    }
+
+    pub fn into_alternate_open_drain(self, speed: Speed) -> Pin<P, N, Alternate<OpenDrain>> {
+        self.into_mode((0b11 << 2) | speed as u32)
+    }
+
+    pub fn into_analog(self) -> Pin<P, N, Analog> {
+        self.into_mode(0b0000)
+    }
+
+    pub const fn port(&self) -> char {
+        P
+    }
+
+    pub const fn pin_number(&self) -> u8 {
+        N
+    }
+}
+
+impl<const P: char, const N: u8, OTYPE> Pin<P, N, Output<OTYPE>> {
+    pub fn set_high(&mut self) {
+        self.gpio().bsrr_write(N);
     }
 
+    pub fn set_low(&mut self) {
+        self.gpio().bsrr_reset(N);
+    }
+
+    pub fn toggle(&mut self) {
+        self.gpio().toggle(N);
+    }
+
+    /// ODR 기준 출력 상태
+    pub fn is_set_high(&self) -> bool {
+        self.gpio().odr_read(N) != 0
+    }
+
+    pub fn is_set_low(&self) -> bool {
+        !self.is_set_high()
+    }
+}
+
+impl<const P: char, const N: u8, PULL> Pin<P, N, Input<PULL>> {
+    pub fn is_high(&self) -> bool {
+        self.gpio().idr_read(N) != 0
+    }
+
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+impl<const P: char, const N: u8> Pin<P, N, Output<OpenDrain>> {
+    /// open-drain 출력은 외부에서 당긴 실제 레벨을 IDR 로 읽을 수 있다
+    pub fn is_high(&self) -> bool {
+        self.gpio().idr_read(N) != 0
+    }
+
+    pub fn is_low(&self) -> bool {
+        !self.is_high()
+    }
+}
+
+/// ## Parts - 포트 하나의 16 개 핀 (reset 상태: floating input)
+/// JTAG 핀 (PA13..PA15, PB3, PB4) 은 reset 직후 실제로는 JTAG 으로 동작하므로 <br/>
+/// AFIO MAPR 의 SWJ_CFG 로 해제한 뒤 사용한다.
+pub struct Parts<const P: char> {
+    pub p0: Pin<P, 0, Input<Floating>>,
+    pub p1: Pin<P, 1, Input<Floating>>,
+    pub p2: Pin<P, 2, Input<Floating>>,
+    pub p3: Pin<P, 3, Input<Floating>>,
+    pub p4: Pin<P, 4, Input<Floating>>,
+    pub p5: Pin<P, 5, Input<Floating>>,
+    pub p6: Pin<P, 6, Input<Floating>>,
+    pub p7: Pin<P, 7, Input<Floating>>,
+    pub p8: Pin<P, 8, Input<Floating>>,
+    pub p9: Pin<P, 9, Input<Floating>>,
+    pub p10: Pin<P, 10, Input<Floating>>,
+    pub p11: Pin<P, 11, Input<Floating>>,
+    pub p12: Pin<P, 12, Input<Floating>>,
+    pub p13: Pin<P, 13, Input<Floating>>,
+    pub p14: Pin<P, 14, Input<Floating>>,
+    pub p15: Pin<P, 15, Input<Floating>>,
+}
+
+impl<const P: char> Parts<P> {
+    pub(super) const fn new() -> Self {
+        Parts {
+            p0: Pin::new(),
+            p1: Pin::new(),
+            p2: Pin::new(),
+            p3: Pin::new(),
+            p4: Pin::new(),
+            p5: Pin::new(),
+            p6: Pin::new(),
+            p7: Pin::new(),
+            p8: Pin::new(),
+            p9: Pin::new(),
+            p10: Pin::new(),
+            p11: Pin::new(),
+            p12: Pin::new(),
+            p13: Pin::new(),
+            p14: Pin::new(),
+            p15: Pin::new(),
+        }
+    }
+}
+
+macro_rules! pin_aliases {
+    ($port:literal: $($alias:ident = $n:literal),+ $(,)?) => {
+        $(pub type $alias<MODE> = Pin<$port, $n, MODE>;)+
+    };
+}
+
+pin_aliases!('A': PA0 = 0, PA1 = 1, PA2 = 2, PA3 = 3, PA4 = 4, PA5 = 5, PA6 = 6, PA7 = 7,
+    PA8 = 8, PA9 = 9, PA10 = 10, PA11 = 11, PA12 = 12, PA13 = 13, PA14 = 14, PA15 = 15);
+pin_aliases!('B': PB0 = 0, PB1 = 1, PB2 = 2, PB3 = 3, PB4 = 4, PB5 = 5, PB6 = 6, PB7 = 7,
+    PB8 = 8, PB9 = 9, PB10 = 10, PB11 = 11, PB12 = 12, PB13 = 13, PB14 = 14, PB15 = 15);
+pin_aliases!('C': PC0 = 0, PC1 = 1, PC2 = 2, PC3 = 3, PC4 = 4, PC5 = 5, PC6 = 6, PC7 = 7,
+    PC8 = 8, PC9 = 9, PC10 = 10, PC11 = 11, PC12 = 12, PC13 = 13, PC14 = 14, PC15 = 15);
+pin_aliases!('D': PD0 = 0, PD1 = 1, PD2 = 2, PD3 = 3, PD4 = 4, PD5 = 5, PD6 = 6, PD7 = 7,
+    PD8 = 8, PD9 = 9, PD10 = 10, PD11 = 11, PD12 = 12, PD13 = 13, PD14 = 14, PD15 = 15);
+pin_aliases!('E': PE0 = 0, PE1 = 1, PE2 = 2, PE3 = 3, PE4 = 4, PE5 = 5, PE6 = 6, PE7 = 7,
+    PE8 = 8, PE9 = 9, PE10 = 10, PE11 = 11, PE12 = 12, PE13 = 13, PE14 = 14, PE15 = 15);
+
+// ---------------------------------------------------------------------------
+// embedded-hal 1.0 (feature = "hal")
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "hal")]
+mod hal {
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::{ErrorType, InputPin, OutputPin, StatefulOutputPin};
+
+    use super::{Input, OpenDrain, Output, Pin};
+
+    impl<const P: char, const N: u8, MODE> ErrorType for Pin<P, N, MODE> {
+        type Error = Infallible;
+    }
+
+    impl<const P: char, const N: u8, OTYPE> OutputPin for Pin<P, N, Output<OTYPE>> {
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Pin::<P, N, Output<OTYPE>>::set_high(self);
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Pin::<P, N, Output<OTYPE>>::set_low(self);
+            Ok(())
+        }
+    }
+
+    impl<const P: char, const N: u8, OTYPE> StatefulOutputPin for Pin<P, N, Output<OTYPE>> {
+        fn is_set_high(&mut self) -> Result<bool, Infallible> {
+            Ok(Pin::<P, N, Output<OTYPE>>::is_set_high(self))
+        }
+
+        fn is_set_low(&mut self) -> Result<bool, Infallible> {
+            Ok(Pin::<P, N, Output<OTYPE>>::is_set_low(self))
+        }
+
+        fn toggle(&mut self) -> Result<(), Infallible> {
+            Pin::<P, N, Output<OTYPE>>::toggle(self);
+            Ok(())
+        }
+    }
+
+    impl<const P: char, const N: u8, PULL> InputPin for Pin<P, N, Input<PULL>> {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(Pin::<P, N, Input<PULL>>::is_high(self))
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(Pin::<P, N, Input<PULL>>::is_low(self))
+        }
+    }
+
+    impl<const P: char, const N: u8> InputPin for Pin<P, N, Output<OpenDrain>> {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(Pin::<P, N, Output<OpenDrain>>::is_high(self))
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(Pin::<P, N, Output<OpenDrain>>::is_low(self))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+    const GPIOB_CRH: u32 = GpioXBase::B as u32 + 0x04;
+    const GPIOA_CRL: u32 = GpioXBase::A as u32;
+
+    #[test]
+    fn crh_port_config_touches_only_its_nibble() {
+        mock::reset();
+        mock::poke(GPIOB_CRH, 0x4444_4444); // reset value: floating input
+        let gpio_b = Gpio::new(GpioXBase::B);
+        gpio_b.crh_port_config(10, 0b1001);
+        gpio_b.crh_port_config(11, 0b1010);
+        assert_eq!(mock::peek(GPIOB_CRH), 0x4444_A944);
+    }
+
+    #[test]
+    fn crl_port_config_touches_only_its_nibble() {
+        mock::reset();
+        mock::poke(GPIOA_CRL, 0x4444_4444);
+        Gpio::new(GpioXBase::A).crl_port_config(5, 0b0001);
+        assert_eq!(mock::peek(GPIOA_CRL), 0x4414_4444);
+    }
+
+    #[test]
+    #[should_panic]
+    fn crh_port_config_rejects_low_pins() {
+        Gpio::new(GpioXBase::B).crh_port_config(7, 0b0001);
+    }
+
+    #[test]
+    fn bsrr_set_and_reset_use_separate_halves() {
+        mock::reset();
+        let gpio_a = Gpio::new(GpioXBase::A);
+        gpio_a.bsrr_write(5);
+        assert_eq!(mock::peek(GpioXBase::A as u32 + 0x10), 1 << 5);
+        gpio_a.bsrr_reset(5);
+        assert_eq!(mock::peek(GpioXBase::A as u32 + 0x10), 1 << 21);
+    }
+
+    #[test]
+    fn idr_read_returns_single_pin() {
+        mock::reset();
+        mock::poke(GpioXBase::C as u32 + 0x08, 1 << 13);
+        let gpio_c = Gpio::new(GpioXBase::C);
+        assert_eq!(gpio_c.idr_read(13), 1);
+        assert_eq!(gpio_c.idr_read(12), 0);
+    }
+
+    #[test]
+    fn bsrr_write_masked_single_store() {
+        mock::reset();
+        let gpio_c = Gpio::new(GpioXBase::C);
+        gpio_c.bsrr_write_masked(0b1111_0000, 0b1010_0101);
+        assert_eq!(mock::writes(GpioXBase::C as u32 + 0x10), [0b1010_0000 | (0b0101_0000 << 16)]);
+    }
+
+    #[test]
+    fn toggle_uses_odr_state() {
+        mock::reset();
+        let bsrr = GpioXBase::A as u32 + 0x10;
+        let gpio_a = Gpio::new(GpioXBase::A);
+        gpio_a.toggle(5);
+        mock::poke(GpioXBase::A as u32 + 0x0C, 1 << 5);
+        assert_eq!(gpio_a.odr_read(5), 1);
+        gpio_a.toggle(5);
+        assert_eq!(mock::writes(bsrr), [1 << 5, 1 << 21]);
+    }
+
+    #[test]
+    fn brr_and_whole_port_reads() {
+        mock::reset();
+        let gpio_d = Gpio::new(GpioXBase::D);
+        gpio_d.brr_write(2);
+        assert_eq!(mock::peek(0x4001_1414), 1 << 2);
+        mock::poke(0x4001_1408, 0xFFFF_A5A5);
+        assert_eq!(gpio_d.idr_read_port(), 0xA5A5);
+    }
+
+    #[test]
+    fn lckr_lock_key_sequence() {
+        mock::reset();
+        let lckr = GpioXBase::B as u32 + 0x18;
+        let gpio_b = Gpio::new(GpioXBase::B);
+        assert!(gpio_b.lckr_lock((1 << 10) | (1 << 11)));
+        let pins = (1 << 10) | (1 << 11);
+        assert_eq!(mock::writes(lckr), [(1 << 16) | pins, pins, (1 << 16) | pins]);
+    }
+
+    #[test]
+    fn typed_pins_write_cnf_mode() {
+        mock::reset();
+        mock::poke(GPIOA_CRL, 0x4444_4444);
+        mock::poke(GPIOB_CRH, 0x4444_4444);
+        let gpioa = Parts::<'A'>::new();
+        let gpiob = Parts::<'B'>::new();
+        let _led: PA5<Output<PushPull>> = gpioa.p5.into_push_pull_output(Speed::Mhz10);
+        let _scl: PB10<Alternate<OpenDrain>> = gpiob.p10.into_alternate_open_drain(Speed::Mhz2);
+        let _sda: PB11<Alternate<OpenDrain>> = gpiob.p11.into_alternate_open_drain(Speed::Mhz2);
+        let _adc: PA0<Analog> = gpioa.p0.into_analog();
+        assert_eq!(mock::peek(GPIOA_CRL), 0x4414_4440);
+        assert_eq!(mock::peek(GPIOB_CRH), 0x4444_EE44);
+    }
+
+    #[test]
+    fn typed_pull_inputs_write_odr_through_bsrr() {
+        mock::reset();
+        let gpioc = Parts::<'C'>::new();
+        let _button: PC13<Input<PullUp>> = gpioc.p13.into_pull_up_input();
+        let _switch: PC2<Input<PullDown>> = gpioc.p2.into_pull_down_input();
+        assert_eq!(mock::writes(GpioXBase::C as u32 + 0x10), [1 << 13, 1 << (2 + 16)]);
+        assert_eq!((mock::peek(GpioXBase::C as u32 + 0x04) >> 20) & 0xF, 0b1000);
+        assert_eq!((mock::peek(GpioXBase::C as u32) >> 8) & 0xF, 0b1000);
+    }
+
+    #[test]
+    fn input_config_pull_ignores_previous_odr() {
+        mock::reset();
+        // BSRR 쓰기를 ODR 에 반영
+        mock::on_write(GpioXBase::C as u32 + 0x10, |v| {
+            let odr = mock::peek(GpioXBase::C as u32 + 0x0C) & !(v >> 16);
+            mock::poke(GpioXBase::C as u32 + 0x0C, odr | (v & 0xFFFF));
+            v
+        });
+        let gpio_c = Gpio::new(GpioXBase::C);
+        mock::poke(GpioXBase::C as u32 + 0x0C, 1 << 13);
+        gpio_c.input_config(13, Pull::Down);
+        assert_eq!(gpio_c.odr_read(13), 0);
+        assert_eq!((mock::peek(GpioXBase::C as u32 + 0x04) >> 20) & 0xF, 0b1000);
+
+        mock::poke(GpioXBase::C as u32 + 0x0C, 0);
+        gpio_c.input_config(13, Pull::Up);
+        assert_eq!(gpio_c.odr_read(13), 1);
+
+        gpio_c.input_config(13, Pull::None);
+        assert_eq!((mock::peek(GpioXBase::C as u32 + 0x04) >> 20) & 0xF, 0b0100);
+        assert_eq!(mock::writes(GpioXBase::C as u32 + 0x10), [1 << 29, 1 << 13]);
+    }
+
+    #[test]
+    fn typed_output_and_input_access() {
+        mock::reset();
+        let gpiod = Parts::<'D'>::new();
+        let mut pd2 = gpiod.p2.into_open_drain_output(Speed::Mhz2);
+        pd2.set_high();
+        pd2.set_low();
+        assert_eq!(mock::writes(GpioXBase::D as u32 + 0x10), [1 << 2, 1 << 18]);
+        mock::poke(GpioXBase::D as u32 + 0x08, 1 << 2);
+        assert!(pd2.is_high());
+        let input = pd2.into_floating_input();
+        assert!(input.is_high());
+        assert_eq!((input.port(), input.pin_number()), ('D', 2));
+    }
 }
@@ -1,207 +1,771 @@
 #![allow(non_snake_case)]
-use rtt_target::rprintln;
+use core::cell::Cell;
+
+use crate::peripherals::{
+    dwt::{self, Deadline},
+    gpio::{Alternate, Gpio, GpioXBase, OpenDrain, Speed, PB10, PB11, PB6, PB7, PB8, PB9},
+    rcc::{ClockDependent, Clocks},
+    register::Reg,
+};
 
 use crate::utils::delay::{delay_sys_clk_ms, delay_sys_clk_10us};
 
 pub enum I2C_BASE {
+    BASE_I2C1 = 0x4000_5400,
     BASE_I2C2 = 0x4000_5800,
 }
+
+/// ## I2cPins - (SCL, SDA) 핀 쌍
+/// F103 의 I2C 핀은 모두 port B. `I2C::init` 이 받아 버스 복구에서 핀 번호를 사용한다. <br/>
+/// `BASE` 는 핀이 연결된 I2C. 다른 I2C 의 `init` 에 넘기면 panic.
+pub trait I2cPins {
+    const BASE: u32;
+    const SCL: u8;
+    const SDA: u8;
+}
+
+/// I2C2: F103 에서 remap 이 없으므로 PB10 SCL / PB11 SDA 고정
+impl I2cPins for (PB10<Alternate<OpenDrain>>, PB11<Alternate<OpenDrain>>) {
+    const BASE: u32 = I2C_BASE::BASE_I2C2 as u32;
+    const SCL: u8 = 10;
+    const SDA: u8 = 11;
+}
+
+impl I2cPins for (PB6<Alternate<OpenDrain>>, PB7<Alternate<OpenDrain>>) {
+    const BASE: u32 = I2C_BASE::BASE_I2C1 as u32;
+    const SCL: u8 = 6;
+    const SDA: u8 = 7;
+}
+
+impl I2cPins for (PB8<Alternate<OpenDrain>>, PB9<Alternate<OpenDrain>>) {
+    const BASE: u32 = I2C_BASE::BASE_I2C1 as u32;
+    const SCL: u8 = 8;
+    const SDA: u8 = 9;
+}
+
+/// ## I2c1Pins - I2C1 SCL / SDA 핀 배치
+/// AFIO MAPR I2C1_REMAP 으로 둘 중 하나를 고른다 (`AFIO::mapr_i2c1_remap`). <br/>
+/// - **(PB6, PB7)**: SCL, SDA. I2C1_REMAP = 0 (리셋 값) <br/>
+/// - **(PB8, PB9)**: SCL, SDA. I2C1_REMAP = 1. Nucleo Arduino 헤더 D15 / D14 <br/>
+pub trait I2c1Pins: I2cPins {
+    const REMAP: bool;
+}
+
+impl I2c1Pins for (PB6<Alternate<OpenDrain>>, PB7<Alternate<OpenDrain>>) {
+    const REMAP: bool = false;
+}
+
+impl I2c1Pins for (PB8<Alternate<OpenDrain>>, PB9<Alternate<OpenDrain>>) {
+    const REMAP: bool = true;
+}
+
+/// SR1 flag 하나를 기다리는 최대 시간. 100 kHz 에서 1 바이트는 약 90 us
+pub const I2C_TIMEOUT_US: u32 = 25_000;
+/// 리셋 직후 HCLK (HSI 8 MHz). `init` / `set_timeout` / `clocks_changed` 전까지 timeout 계산에 사용
+const RESET_HCLK: u32 = 8_000_000;
+
+const SR1_SB: u8 = 0;
+const SR1_ADDR: u8 = 1;
+const SR1_BTF: u8 = 2;
+const SR1_RXNE: u8 = 6;
+const SR1_TXE: u8 = 7;
+const SR1_BERR: u32 = 1 << 8;
+const SR1_ARLO: u32 = 1 << 9;
+const SR1_AF: u32 = 1 << 10;
+/// BERR | ARLO | AF | OVR
+const SR1_ERRORS: u32 = 0b1111 << 8;
+const SR2_MSL: u8 = 0;
+const SR2_BUSY: u8 = 1;
+/// 버스 복구 시 SDA 를 풀기 위해 보내는 최대 SCL 클럭 수 (바이트 8 bit + ACK)
+const RECOVERY_CLOCKS: u32 = 9;
+
+/// NACK 을 받은 단계
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NackPhase {
+    /// slave 주소에 응답하는 장치가 없음
+    Address,
+    /// slave 가 데이터 바이트를 거부
+    Data,
+}
+
+/// ## I2cError
+/// - **Bus**: BERR, 잘못된 위치의 START / STOP <br/>
+/// - **ArbitrationLost**: ARLO, 다른 master 에게 버스를 빼앗김 <br/>
+/// - **Nack**: AF <br/>
+/// - **Overrun**: OVR <br/>
+/// - **Timeout**: flag 가 `I2C_TIMEOUT_US` 안에 set 되지 않음 (SCL / SDA 가 잡혀 있는 경우 등) <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum I2cError {
+    Bus,
+    ArbitrationLost,
+    Nack(NackPhase),
+    Overrun,
+    Timeout,
+}
+
+/// ## DutyCycle - Fast mode SCL low / high 비
+/// - **Ratio2to1**: T_low / T_high = 2, SCL = PCLK1 / (3 x CCR) <br/>
+/// - **Ratio16to9**: T_low / T_high = 16 / 9, SCL = PCLK1 / (25 x CCR). 400 kHz 를 내려면 PCLK1 이 10 MHz 의 배수여야 한다. <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DutyCycle {
+    Ratio2to1,
+    Ratio16to9,
+}
+
+/// ## Mode - SCL 속도
+/// - **Standard**: 100 kHz <br/>
+/// - **Fast**: 400 kHz <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    Standard,
+    Fast(DutyCycle),
+}
+
+impl Mode {
+    pub const fn scl(&self) -> u32 {
+        match self {
+            Mode::Standard => 100_000,
+            Mode::Fast(_) => 400_000,
+        }
+    }
+
+    /// SCL 한 주기의 PCLK1 cycle 수 = 이 값 x CCR
+    const fn ccr_divider(&self) -> u32 {
+        match self {
+            Mode::Standard => 2,
+            Mode::Fast(DutyCycle::Ratio2to1) => 3,
+            Mode::Fast(DutyCycle::Ratio16to9) => 25,
+        }
+    }
+
+    /// Standard 는 CCR 최소 4, Fast 는 1
+    const fn min_ccr(&self) -> u32 {
+        match self {
+            Mode::Standard => 4,
+            Mode::Fast(_) => 1,
+        }
+    }
+
+    /// SCL 최대 rise time (ns)
+    const fn max_rise_ns(&self) -> u32 {
+        match self {
+            Mode::Standard => 1000,
+            Mode::Fast(_) => 300,
+        }
+    }
+}
+
+/// ## TimingError
+/// `init` / `set_timing` 에 준 PCLK1 과 `Mode` 로 SCL 을 만들 수 없는 경우
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimingError {
+    /// PCLK1 은 Standard 2 ~ 36 MHz, Fast 4 ~ 36 MHz (FREQ 범위)
+    Pclk1OutOfRange,
+    /// CCR 최소값으로도 요청한 SCL 보다 느림 (ex. 16:9 duty, PCLK1 < 10 MHz)
+    SpeedUnreachable,
+}
+
+/// ## InitError
+/// - **Timing**: PCLK1 과 `Mode` 로 SCL 을 만들 수 없음 (레지스터는 건드리지 않음) <br/>
+/// - **Bus**: BUSY 였고 `recover_bus` 로도 버스를 풀지 못함 (SDA 가 계속 low 등) <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InitError {
+    Timing(TimingError),
+    Bus(I2cError),
+}
+
+impl From<TimingError> for InitError {
+    fn from(error: TimingError) -> Self {
+        InitError::Timing(error)
+    }
+}
+
+impl From<I2cError> for InitError {
+    fn from(error: I2cError) -> Self {
+        InitError::Bus(error)
+    }
+}
+
+/// ## I2cTiming - FREQ, CCR, TRISE 레지스터 값
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct I2cTiming {
+    pub freq: u32,
+    pub ccr: u32,
+    pub trise: u32,
+}
+
+const PCLK1_MAX: u32 = 36_000_000;
+
+impl I2cTiming {
+    /// ## compute
+    /// - FREQ = PCLK1 (MHz) <br/>
+    /// - CCR = PCLK1 / (divider x SCL) 를 올림 (SCL 이 요청보다 빨라지지 않게) <br/>
+    /// - TRISE = max rise time / T_PCLK1 + 1 <br/>
+    pub const fn compute(pclk1: u32, mode: Mode) -> Result<I2cTiming, TimingError> {
+        let min_pclk1 = match mode {
+            Mode::Standard => 2_000_000,
+            Mode::Fast(_) => 4_000_000,
+        };
+        if pclk1 < min_pclk1 || pclk1 > PCLK1_MAX {
+            return Err(TimingError::Pclk1OutOfRange);
+        }
+        // 최소 CCR 에서의 SCL 이 가장 빠른 속도
+        if pclk1 / (mode.ccr_divider() * mode.min_ccr()) < mode.scl() {
+            return Err(TimingError::SpeedUnreachable);
+        }
+        let freq = pclk1 / 1_000_000;
+        Ok(I2cTiming {
+            freq,
+            ccr: pclk1.div_ceil(mode.ccr_divider() * mode.scl()),
+            trise: freq * mode.max_rise_ns() / 1000 + 1,
+        })
+    }
+}
+
 pub struct I2C {
     base: u32,
+    /// SR1 flag 대기 한도 (HCLK cycle, DWT CYCCNT 기준)
+    timeout_cycles: Cell<u32>,
+    /// `clocks_changed` 에서 다시 계산할 SCL 모드
+    mode: Cell<Mode>,
+    /// 버스 복구에 쓸 (SCL, SDA) 핀 번호 (port B). `init` 이 설정한다
+    bus_pins: Cell<Option<(u8, u8)>>,
 }
 
 impl I2C {
-    pub fn new(base: I2C_BASE) -> I2C {
-        I2C { base: base as u32 }
+    pub(super) fn new(base: I2C_BASE) -> I2C {
+        I2C {
+            base: base as u32,
+            timeout_cycles: Cell::new(dwt::us_to_cycles(RESET_HCLK, I2C_TIMEOUT_US)),
+            mode: Cell::new(Mode::Standard),
+            bus_pins: Cell::new(None),
+        }
     }
-    fn CR1(&self) -> *mut u32 {
-        (self.base + 0x00) as *mut u32
+    fn CR1(&self) -> Reg {
+        Reg::at(self.base, 0x00)
     }
-    fn CR2(&self) -> *mut u32 {
-        (self.base + 0x04) as *mut u32
+    fn CR2(&self) -> Reg {
+        Reg::at(self.base, 0x04)
     }
-    fn CCR(&self) -> *mut u32 {
-        (self.base + 0x1C) as *mut u32
+    fn CCR(&self) -> Reg {
+        Reg::at(self.base, 0x1C)
     }
-    fn TRISE(&self) -> *mut u32 {
-        (self.base + 0x20) as *mut u32
+    fn TRISE(&self) -> Reg {
+        Reg::at(self.base, 0x20)
     }
-    fn SR1(&self) -> *mut u32 {
-        (self.base + 0x14) as *mut u32
+    fn SR1(&self) -> Reg {
+        Reg::at(self.base, 0x14)
     }
-    fn SR2(&self) -> *mut u32 {
-        (self.base + 0x18) as *mut u32
+    fn SR2(&self) -> Reg {
+        Reg::at(self.base, 0x18)
     }
-    fn DR(&self) -> *mut u32 {
-        (self.base + 0x10) as *mut u32
+    fn DR(&self) -> Reg {
+        Reg::at(self.base, 0x10)
     }
     pub fn cr1_pe(&self, enable: bool) {
-        unsafe {
-            let mut cr1_val = self.CR1().read_volatile();
-            if enable {
-                cr1_val |= (1 << 0); // Enable I2C
-            } else {
-                cr1_val &= !(1 << 0); // Disable I2C
-            }
-            self.CR1().write_volatile(cr1_val);
-        }
+        self.CR1().bit_band(0).write(enable); // Enable / Disable I2C
     }
     pub fn cr2_freq(&self, freq: u32) {
         if (freq > 0b110010) {
             panic!("FREQ value is out of range FREQ > 50MHz NOT ALLOWED");
         };
-        unsafe {
-            let mut cr2_val = self.CR2().read_volatile();
-            cr2_val &= !(0b111111 << 0); // Clear FREQ[5:0]
-            cr2_val |= (freq << 0); // Set FREQ[5:0] to 8MHz
-            self.CR2().write_volatile(cr2_val);
-        }
+        self.CR2().write_field(0, 0b111111, freq); // Set FREQ[5:0]
     }
     pub fn ccr_set(&self, ccr: u32) {
-        unsafe {
-            let mut ccr_val = self.CCR().read_volatile();
-            ccr_val &= !(0b1111_1111_1111 << 0); // Clear CCR[11:0]
-            ccr_val |= (ccr << 0);
-            self.CCR().write_volatile(ccr_val);
-        }
+        self.CCR().write_field(0, 0b1111_1111_1111, ccr); // Set CCR[11:0]
     }
     pub fn trise_set(&self, trise: u32) {
-        unsafe {
-            let mut trise_val = self.TRISE().read_volatile();
-            trise_val &= !(0b11111 << 0); // Clear TRISE[5:0]
-            trise_val |= (trise << 0);
-            self.TRISE().write_volatile(trise_val);
-        }
+        self.TRISE().write_field(0, 0b111111, trise); // Set TRISE[5:0]
     }
-    pub fn init(&self) {
+    /// ## init
+    /// `clocks` 의 PCLK1 과 `mode` 로 timing 을 설정하고 I2C 를 켠다. 만들 수 없는 조합이면 레지스터를 건드리지 않고 에러. <br/>
+    /// timeout 도 `clocks` 의 HCLK 로 `I2C_TIMEOUT_US` 에 맞춘다 (init 안의 버스 복구부터 적용). <br/>
+    /// `pins` 는 이 I2C 의 AF open-drain 핀 쌍으로, 버스 복구 때 GPIO 로 직접 움직인다. 다른 I2C 의 핀이면 panic.
+    /// ```ignore
+    /// let pins = (gpio_b.p10.into_alternate_open_drain(Speed::Mhz2), gpio_b.p11.into_alternate_open_drain(Speed::Mhz2));
+    /// i2c2.init(&clocks, Mode::Standard, &pins)?;
+    /// ```
+    pub fn init<PINS: I2cPins>(&self, clocks: &Clocks, mode: Mode, _pins: &PINS) -> Result<(), InitError> {
+        assert_eq!(PINS::BASE, self.base, "I2C pins belong to another I2C peripheral");
+        let timing = I2cTiming::compute(clocks.pclk1(), mode)?;
+        self.bus_pins.set(Some((PINS::SCL, PINS::SDA)));
+        self.set_timeout(clocks.hclk(), I2C_TIMEOUT_US);
         self.cr1_pe(false); // Disable I2C
-        self.cr2_freq(8); // Set FREQ[5:0] to 8MHz
-        self.ccr_set(40); // Set CCR[11:0] to 40 (Standard mode, 100 kHz)
-        self.trise_set(9); // Set TRISE[5:0] to 9
+        self.write_timing(timing, mode);
+        self.mode.set(mode);
         self.cr1_pe(true); // Enable I2C
 
         delay_sys_clk_ms(50);
+        // 리셋 전 transfer 가 끊겨 slave 가 SDA 를 잡고 있으면 BUSY
+        self.ensure_idle()?;
+        Ok(())
+    }
+    /// ## set_timing
+    /// 클럭 변경 후 같은 I2C 를 새 PCLK1 에 맞춘다. <br/>
+    /// CCR 은 PE=0 일 때만 쓸 수 있으므로 잠시 끄고, 원래 켜져 있었으면 다시 켠다.
+    pub fn set_timing(&self, pclk1: u32, mode: Mode) -> Result<(), TimingError> {
+        let timing = I2cTiming::compute(pclk1, mode)?;
+        let was_enabled = self.CR1().read_bit(0);
+        self.cr1_pe(false);
+        self.write_timing(timing, mode);
+        self.mode.set(mode);
+        self.cr1_pe(was_enabled);
+        Ok(())
     }
-    pub fn cr1_start(&self) {
-        unsafe {
-            let mut cr1_val = self.CR1().read_volatile();
-            cr1_val |= (1 << 8); // Set the START bit (bit 8)
-            self.CR1().write_volatile(cr1_val);
-            while (self.SR1().read_volatile() & (1 << 0)) == 0 {} // Wait until the START condition is generated (SB bit is set in SR1)
+
+    /// PE=0 상태에서 호출. CCR 의 F/S (bit 15), DUTY (bit 14) 도 함께 쓴다.
+    fn write_timing(&self, timing: I2cTiming, mode: Mode) {
+        self.cr2_freq(timing.freq);
+        let (fast, duty) = match mode {
+            Mode::Standard => (false, false),
+            Mode::Fast(duty) => (true, duty == DutyCycle::Ratio16to9),
+        };
+        self.CCR().write_field(14, 0b11, ((fast as u32) << 1) | duty as u32);
+        self.ccr_set(timing.ccr);
+        self.trise_set(timing.trise);
+    }
+
+    /// ## set_timeout
+    /// flag 하나를 기다리는 최대 시간. CYCCNT 는 HCLK 로 세므로 현재 HCLK 가 필요하다.
+    pub fn set_timeout(&self, hclk: u32, timeout_us: u32) {
+        self.timeout_cycles.set(dwt::us_to_cycles(hclk, timeout_us));
+    }
+
+    pub fn cr1_start(&self) -> Result<(), I2cError> {
+        self.CR1().bit_band(8).set(); // Set the START bit (bit 8)
+        self.wait_sr1(SR1_SB, NackPhase::Address) // Wait until the START condition is generated (SB bit is set in SR1)
+    }
+    /// ## cr1_stop
+    /// STOP 을 예약하고 stop condition 이 나가 하드웨어가 STOP 을 0 으로 되돌릴 때까지 기다린다.
+    pub fn cr1_stop(&self) -> Result<(), I2cError> {
+        self.CR1().bit_band(9).set(); // Set the STOP bit (bit 9)
+        self.wait_stop_cleared()
+    }
+
+    /// ## wait_stop_cleared
+    /// STOP / START 가 pending 인 동안 CR1 을 쓰면 condition 이 빠지거나 두 번 나간다 (RM0008 26.6.1). <br/>
+    /// STOP 을 쓴 뒤에는 CR1 을 건드리거나 새 START 를 내기 전에 여기서 기다린다.
+    fn wait_stop_cleared(&self) -> Result<(), I2cError> {
+        let deadline = Deadline::after_cycles(self.timeout_cycles.get());
+        while self.CR1().read_bit(9) {
+            if deadline.expired() {
+                return Err(I2cError::Timeout);
+            }
         }
+        Ok(())
     }
-    pub fn cr1_stop(&self) {
-        unsafe {
-            let mut cr1_val = self.CR1().read_volatile();
-            cr1_val |= (1 << 9); // Set the STOP bit (bit 9)
-            self.CR1().write_volatile(cr1_val);
+
+    /// ## wait_sr1
+    /// SR1 의 `bit` 가 set 될 때까지 대기. <br/>
+    /// 에러 flag 는 rc_w0 이므로 해당 비트에만 0 을 써서 클리어하고 (나머지는 1 = 변화 없음) `I2cError` 로 반환한다.
+    fn wait_sr1(&self, bit: u8, phase: NackPhase) -> Result<(), I2cError> {
+        let deadline = Deadline::after_cycles(self.timeout_cycles.get());
+        loop {
+            let sr1 = self.SR1().read();
+            if sr1 & SR1_ERRORS != 0 {
+                self.SR1().write(!(sr1 & SR1_ERRORS) & 0xFFFF);
+                return Err(match sr1 {
+                    v if v & SR1_BERR != 0 => I2cError::Bus,
+                    v if v & SR1_ARLO != 0 => I2cError::ArbitrationLost,
+                    v if v & SR1_AF != 0 => I2cError::Nack(phase),
+                    _ => I2cError::Overrun,
+                });
+            }
+            if sr1 & (1 << bit) != 0 {
+                return Ok(());
+            }
+            if deadline.expired() {
+                return Err(I2cError::Timeout);
+            }
         }
     }
 
-pub fn dr_write(&self, address: u8, data: u8) {
-    self.cr1_start();
-    // rprintln!("I2C start condition set");
+    /// (RE)START + 7-bit address. ADDR 은 클리어하지 않는다.
+    fn start_address(&self, address: u8, read: bool) -> Result<(), I2cError> {
+        self.CR1().bit_band(8).set(); // START
+        self.send_address(address, read)
+    }
 
-    let address_write = address << 1;
-    unsafe {
+    /// START 가 이미 예약된 상태 (read 끝의 RESTART) 에서 SB 를 기다려 address 를 보낸다
+    fn send_address(&self, address: u8, read: bool) -> Result<(), I2cError> {
+        self.wait_sr1(SR1_SB, NackPhase::Address)?;
+        self.DR().write(((address << 1) | read as u8) as u32);
+        self.wait_sr1(SR1_ADDR, NackPhase::Address)
+    }
 
-        self.DR().write_volatile(address_write.into());
-        // rprintln!("I2C address written: 0x{:X}", address_write);
+    /// ADDR 클리어: SR1 읽기 후 SR2 읽기
+    fn clear_addr(&self) {
+        let _ = self.SR1().read();
+        let _ = self.SR2().read();
+    }
 
-        // Wait until the ADDR bit is set in SR1
-        let mut timeout = 1000000000; // 타임아웃 카운터 설정
-        while (self.SR1().read_volatile() & (1 << 1)) == 0 {
-            // rprintln!("Waiting for ADDR bit to be set");
+    fn write_bytes(&self, bytes: &[u8]) -> Result<(), I2cError> {
+        if bytes.is_empty() {
+            return Ok(()); // address 만 보내는 probe
+        }
+        for &byte in bytes {
+            self.wait_sr1(SR1_TXE, NackPhase::Data)?;
+            self.DR().write(byte as u32);
+        }
+        self.wait_sr1(SR1_BTF, NackPhase::Data)
+    }
 
-            // Check for errors
-            let sr1_val = self.SR1().read_volatile();
-            if sr1_val & (1 << 8) != 0 {
-                rprintln!("I2C Bus Error");
-                break;
+    /// ## read_bytes
+    /// address (read) 의 ADDR 이 set 된 상태에서 `len` 바이트를 받아 `bytes` 에 채운다 (RM0008 26.3.3). <br/>
+    /// - **1 바이트**: ADDR 클리어 전에 ACK=0, 클리어 직후 STOP <br/>
+    /// - **2 바이트**: POS=1, ACK=1 → ADDR 클리어 → ACK=0 → BTF (두 바이트 모두 수신) → POS=0 → STOP → DR 두 번 <br/>
+    /// - **N 바이트**: N-3 까지 RxNE 마다 읽고, BTF (N-2 는 DR, N-1 은 shift) → ACK=0 → DR → STOP → DR → RxNE → DR <br/>
+    ///
+    /// STOP 을 늦게 쓰면 한 바이트가 더 클럭되므로 (errata 2.13.2) 해당 구간은 critical section 안에서 실행한다. <br/>
+    /// `end` 가 `Restart` 면 STOP 대신 START 를 예약한다. STOP 이면 하드웨어가 STOP 을 지울 때까지 기다린 뒤 돌아온다.
+    fn read_bytes<'a>(&self, len: usize, bytes: impl Iterator<Item = &'a mut u8>, end: End) -> Result<(), I2cError> {
+        self.receive(len, bytes, end)?;
+        if end == End::Stop {
+            self.wait_stop_cleared()?;
+        }
+        Ok(())
+    }
+
+    /// `read_bytes` 의 수신 순서. STOP / START 예약 이후에는 CR1 을 쓰지 않는다.
+    fn receive<'a>(&self, len: usize, mut bytes: impl Iterator<Item = &'a mut u8>, end: End) -> Result<(), I2cError> {
+        let end_bit = match end {
+            End::Stop => 9,
+            End::Restart => 8,
+        };
+        let mut store = |value: u32| {
+            if let Some(byte) = bytes.next() {
+                *byte = value as u8;
             }
-            if sr1_val & (1 << 9) != 0 {
-                rprintln!("I2C Arbitration Lost");
-                break;
+        };
+        match len {
+            0 => Ok(()),
+            1 => {
+                self.CR1().bit_band(10).clear(); // ACK
+                critical_section::with(|_| {
+                    self.clear_addr();
+                    self.CR1().bit_band(end_bit).set();
+                });
+                self.wait_sr1(SR1_RXNE, NackPhase::Data)?;
+                store(self.DR().read());
+                Ok(())
             }
-            if sr1_val & (1 << 10) != 0 {
-                rprintln!("I2C Acknowledge Failure");
-                break;
+            2 => {
+                self.CR1().bit_band(11).set(); // POS: ACK 는 shift 의 다음 바이트에 적용
+                self.CR1().bit_band(10).set();
+                critical_section::with(|_| {
+                    self.clear_addr();
+                    self.CR1().bit_band(10).clear();
+                });
+                let result = self.wait_sr1(SR1_BTF, NackPhase::Data);
+                // NACK 은 이미 나갔으므로 STOP 을 쓰기 전에 POS 를 되돌린다
+                self.CR1().bit_band(11).clear();
+                if result.is_ok() {
+                    critical_section::with(|_| {
+                        self.CR1().bit_band(end_bit).set();
+                        store(self.DR().read());
+                    });
+                    store(self.DR().read());
+                }
+                result
             }
-            if sr1_val & (1 << 11) != 0 {
-                rprintln!("I2C Overrun/Underrun");
-                break;
+            _ => {
+                self.CR1().bit_band(10).set();
+                self.clear_addr();
+                for _ in 0..len - 3 {
+                    self.wait_sr1(SR1_RXNE, NackPhase::Data)?;
+                    store(self.DR().read());
+                }
+                self.wait_sr1(SR1_BTF, NackPhase::Data)?;
+                self.CR1().bit_band(10).clear();
+                critical_section::with(|_| {
+                    store(self.DR().read()); // N-2
+                    self.CR1().bit_band(end_bit).set();
+                    store(self.DR().read()); // N-1
+                });
+                self.wait_sr1(SR1_RXNE, NackPhase::Data)?;
+                store(self.DR().read()); // N
+                Ok(())
             }
+        }
+    }
 
-            // 타임아웃 체크
-            timeout -= 1;
-            if timeout == 0 {
-                rprintln!("Timeout waiting for ADDR bit to be set");
-                break;
-            }
+    /// ## finish
+    /// 성공 / 실패와 관계없이 STOP 으로 버스를 놓는다. ARLO 로 이미 slave 모드가 된 경우에도 STOP 은 SCL / SDA 를 풀어 준다. <br/>
+    /// timeout 이거나 STOP 이 끝나지 않으면 STOP 만으로는 풀리지 않으므로 `recover_bus` 까지 실행한다 (SWRST 가 STOP 도 지운다).
+    fn finish<T>(&self, result: Result<T, I2cError>) -> Result<T, I2cError> {
+        let stopped = self.cr1_stop();
+        if stopped.is_err() || matches!(result, Err(I2cError::Timeout)) {
+            let _ = self.recover_bus();
         }
+        result.and_then(|value| stopped.map(|()| value))
+    }
 
-        // ADDR 비트가 설정되지 않으면 함수 종료
-        if self.SR1().read_volatile() & (1 << 1) == 0 {
-            self.cr1_stop();
-            return;
+    /// read 는 성공하면 `read_bytes` 가 이미 STOP 을 보냈으므로 실패했을 때만 STOP
+    fn stop_on_error<T>(&self, result: Result<T, I2cError>) -> Result<T, I2cError> {
+        if result.is_err() {
+            self.finish(result)
+        } else {
+            result
         }
-        
-        // rprintln!("I2C address acknowledged");
+    }
 
-        // Clear ADDR flag
-        let _ = self.SR1().read_volatile();
-        let _ = self.SR2().read_volatile();
-        // rprintln!("I2C address cleared");
+    /// ## ensure_idle
+    /// `init` 과 transfer 시작 전: master 가 아닌데 (MSL=0) BUSY 면 버스가 멈춘 것으로 보고 복구한다. <br/>
+    /// 복구 후에도 BUSY 면 `I2cError::Bus`.
+    fn ensure_idle(&self) -> Result<(), I2cError> {
+        if self.SR2().read_bit(SR2_BUSY) && !self.SR2().read_bit(SR2_MSL) {
+            self.recover_bus()?;
+            if self.SR2().read_bit(SR2_BUSY) {
+                return Err(I2cError::Bus);
+            }
+        }
+        Ok(())
+    }
 
-        self.DR().write_volatile(data.into());
-        // rprintln!("I2C data written: 0x{:X}", data);
+    /// ## recover_bus
+    /// transfer 중 MCU 가 리셋되어 slave 가 SDA 를 low 로 잡고 있을 때 버스를 풀어 준다. <br/>
+    /// 1. SCL / SDA 를 GPIO open-drain 출력 (high) 으로 전환 <br/>
+    /// 2. SDA 가 high 가 될 때까지 SCL 을 최대 9 번 클럭 (slave 가 보내던 바이트를 끝까지 내보내게 함) <br/>
+    /// 3. SCL high 동안 SDA low → high 로 STOP <br/>
+    /// 4. 핀을 원래 (AF open-drain) 설정으로 되돌림 <br/>
+    /// 5. CR1 SWRST 로 멈춘 BUSY 를 지우고 CR2 / CCR / TRISE / PE 를 다시 씀 <br/>
+    ///
+    /// SDA 가 끝까지 low 이거나, `init` 전이라 핀을 모르면 `I2cError::Bus`.
+    pub fn recover_bus(&self) -> Result<(), I2cError> {
+        let Some((scl, sda)) = self.bus_pins.get() else {
+            return Err(I2cError::Bus);
+        };
+        let released = self.release_sda(scl, sda);
+        self.software_reset();
+        if released {
+            Ok(())
+        } else {
+            Err(I2cError::Bus)
+        }
+    }
 
-        // Wait until the BTF bit is set in SR1
-        timeout = 1000000000; // 타임아웃 카운터 재설정
-        while (self.SR1().read_volatile() & (1 << 7)) == 0 {
-            rprintln!("Waiting for BTF bit to be set");
+    /// `recover_bus` 1 ~ 4. 반 주기 10 us (약 50 kHz)
+    fn release_sda(&self, scl: u8, sda: u8) -> bool {
+        let gpio = Gpio::new(GpioXBase::B);
+        let saved = (gpio.read_port_config(scl), gpio.read_port_config(sda));
+        // 출력으로 바꾸기 전에 ODR = 1 (released) 로 두어 low glitch 가 없게 한다
+        gpio.bsrr_write_masked((1 << scl) | (1 << sda), 0xFFFF);
+        let open_drain = (0b01 << 2) | Speed::Mhz2 as u32;
+        gpio.port_config(scl, open_drain);
+        gpio.port_config(sda, open_drain);
 
-            // Check for errors
-            let sr1_val = self.SR1().read_volatile();
-            if sr1_val & (1 << 8) != 0 {
-                rprintln!("I2C Bus Error");
-                break;
-            }
-            if sr1_val & (1 << 9) != 0 {
-                rprintln!("I2C Arbitration Lost");
-                break;
-            }
-            if sr1_val & (1 << 10) != 0 {
-                rprintln!("I2C Acknowledge Failure");
+        let scl_high = || {
+            gpio.bsrr_write(scl);
+            // slave 의 clock stretching 대기
+            let deadline = Deadline::after_cycles(self.timeout_cycles.get());
+            while gpio.idr_read(scl) == 0 && !deadline.expired() {}
+            delay_sys_clk_10us(1);
+        };
+        let scl_low = || {
+            gpio.bsrr_reset(scl);
+            delay_sys_clk_10us(1);
+        };
+
+        for _ in 0..RECOVERY_CLOCKS {
+            if gpio.idr_read(sda) != 0 {
                 break;
             }
-            if sr1_val & (1 << 11) != 0 {
-                rprintln!("I2C Overrun/Underrun");
-                break;
+            scl_low();
+            scl_high();
+        }
+        // STOP: SCL low 에서 SDA low, SCL high 후 SDA high
+        scl_low();
+        gpio.bsrr_reset(sda);
+        delay_sys_clk_10us(1);
+        scl_high();
+        gpio.bsrr_write(sda);
+        delay_sys_clk_10us(1);
+        let released = gpio.idr_read(sda) != 0;
+
+        gpio.port_config(scl, saved.0);
+        gpio.port_config(sda, saved.1);
+        released
+    }
+
+    /// SWRST 는 모든 I2C 레지스터를 리셋하므로 timing 설정을 저장했다가 다시 쓴다
+    fn software_reset(&self) {
+        let was_enabled = self.CR1().read_bit(0);
+        let (cr2, ccr, trise) = (self.CR2().read(), self.CCR().read(), self.TRISE().read());
+        self.CR1().bit_band(15).set(); // SWRST
+        self.CR1().bit_band(15).clear();
+        self.CR2().write(cr2);
+        self.CCR().write(ccr);
+        self.TRISE().write(trise);
+        self.cr1_pe(was_enabled);
+    }
+
+    /// ## write
+    /// START, address (W), `bytes`, STOP. `bytes` 가 비어 있으면 address 만 보내 장치가 있는지 확인한다.
+    pub fn write(&self, address: u8, bytes: &[u8]) -> Result<(), I2cError> {
+        let result = self.ensure_idle().and_then(|()| self.start_address(address, false)).and_then(|()| {
+            self.clear_addr();
+            self.write_bytes(bytes)
+        });
+        self.finish(result)
+    }
+
+    /// ## read
+    /// START, address (R), `buf.len()` 바이트, 마지막 바이트 NACK, STOP. <br/>
+    /// 0 바이트 read 는 하드웨어가 지원하지 않으므로 빈 `buf` 는 `write(address, &[])` 와 같다.
+    pub fn read(&self, address: u8, buf: &mut [u8]) -> Result<(), I2cError> {
+        if buf.is_empty() {
+            return self.write(address, &[]);
+        }
+        let result = self
+            .ensure_idle()
+            .and_then(|()| self.start_address(address, true))
+            .and_then(|()| self.read_bytes(buf.len(), buf.iter_mut(), End::Stop));
+        self.stop_on_error(result)
+    }
+
+    /// ## write_read
+    /// `bytes` 를 쓴 뒤 STOP 없이 repeated START 로 `buf` 를 읽는다 (레지스터 주소 → 값 읽기).
+    pub fn write_read(&self, address: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), I2cError> {
+        if buf.is_empty() {
+            return self.write(address, bytes);
+        }
+        let result = self
+            .ensure_idle()
+            .and_then(|()| self.start_address(address, false))
+            .and_then(|()| {
+                self.clear_addr();
+                self.write_bytes(bytes)
+            })
+            .and_then(|()| self.start_address(address, true))
+            .and_then(|()| self.read_bytes(buf.len(), buf.iter_mut(), End::Stop));
+        self.stop_on_error(result)
+    }
+
+    /// ## dr_write
+    /// `address` 에 `data` 한 바이트를 쓴다 (START, address, data, STOP).
+    pub fn dr_write(&self, address: u8, data: u8) -> Result<(), I2cError> {
+        self.write(address, &[data])
+    }
+}
+
+/// read 를 끝낸 뒤 보낼 condition
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum End {
+    Stop,
+    /// 다음 write 를 위한 repeated START
+    Restart,
+}
+
+// ---------------------------------------------------------------------------
+// embedded-hal 1.0 (feature = "hal")
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "hal")]
+mod hal {
+    use embedded_hal::i2c::{Error, ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation, SevenBitAddress};
+
+    use super::{End, I2cError, NackPhase, I2C};
+
+    impl Error for I2cError {
+        fn kind(&self) -> ErrorKind {
+            match self {
+                I2cError::Bus => ErrorKind::Bus,
+                I2cError::ArbitrationLost => ErrorKind::ArbitrationLoss,
+                I2cError::Nack(NackPhase::Address) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+                I2cError::Nack(NackPhase::Data) => ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+                I2cError::Overrun => ErrorKind::Overrun,
+                I2cError::Timeout => ErrorKind::Other,
             }
+        }
+    }
 
-            // 타임아웃 체크
-            timeout -= 1;
-            if timeout == 0 {
-                rprintln!("Timeout waiting for BTF bit to be set");
-                break;
+    /// 0 바이트 read 는 하드웨어가 지원하지 않으므로 빈 write 처럼 취급한다
+    fn is_read(op: &Operation<'_>) -> bool {
+        matches!(op, Operation::Read(buf) if !buf.is_empty())
+    }
+
+    impl I2C {
+        /// ## run
+        /// 같은 방향의 연속된 operation 은 START 하나로 묶는다. write 묶음은 `write_bytes`, <br/>
+        /// read 묶음은 버퍼들을 이어 붙여 `read_bytes` 로 받는다 (마지막 묶음이면 STOP, 아니면 RESTART).
+        fn run(&self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), I2cError> {
+            let count = operations.len();
+            let mut start = 0;
+            let mut restart_requested = false;
+            while start < count {
+                let reading = is_read(&operations[start]);
+                let end = (start..count)
+                    .find(|&j| is_read(&operations[j]) != reading)
+                    .unwrap_or(count);
+                if restart_requested {
+                    self.send_address(address, reading)?;
+                } else {
+                    self.start_address(address, reading)?;
+                }
+                let group = &mut operations[start..end];
+                if reading {
+                    let len = group.iter().map(|op| match op {
+                        Operation::Read(buf) => buf.len(),
+                        Operation::Write(_) => 0,
+                    });
+                    let len = len.sum();
+                    let bytes = group.iter_mut().flat_map(|op| match op {
+                        Operation::Read(buf) => buf.iter_mut(),
+                        Operation::Write(_) => Default::default(),
+                    });
+                    let last = if end == count { End::Stop } else { End::Restart };
+                    self.read_bytes(len, bytes, last)?;
+                    restart_requested = last == End::Restart;
+                } else {
+                    self.clear_addr();
+                    for op in group.iter() {
+                        if let Operation::Write(bytes) = op {
+                            self.write_bytes(bytes)?;
+                        }
+                    }
+                    restart_requested = false;
+                    if end == count {
+                        self.cr1_stop()?;
+                    }
+                }
+                start = end;
             }
+            Ok(())
+        }
+    }
+
+    impl ErrorType for I2C {
+        type Error = I2cError;
+    }
+
+    impl I2c<SevenBitAddress> for I2C {
+        fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), I2cError> {
+            let result = self.ensure_idle().and_then(|()| self.run(address, operations));
+            self.stop_on_error(result)
+        }
+
+        fn read(&mut self, address: u8, read: &mut [u8]) -> Result<(), I2cError> {
+            I2C::read(self, address, read)
         }
 
-        // BTF 비트가 설정되지 않으면 함수 종료
-        if  self.SR1().read_volatile() & (1 << 7) == 0 {
-            self.cr1_stop();
-            return;
+        fn write(&mut self, address: u8, write: &[u8]) -> Result<(), I2cError> {
+            I2C::write(self, address, write)
         }
-        
-        // rprintln!("I2C data transfer finished");
 
-        self.cr1_stop();
-        // rprintln!("I2C stop condition set");
+        fn write_read(&mut self, address: u8, write: &[u8], read: &mut [u8]) -> Result<(), I2cError> {
+            I2C::write_read(self, address, write, read)
+        }
     }
 }
 
-
+impl ClockDependent for I2C {
+    /// 새 PCLK1 로 지금 모드를 만들 수 없으면 (ex. HSE 고장 후 PCLK1 이 낮아져 16:9 Fast 불가) Standard 로 내린다
+    fn clocks_changed(&self, clocks: &Clocks) {
+        if self.set_timing(clocks.pclk1(), self.mode.get()).is_err() {
+            let _ = self.set_timing(clocks.pclk1(), Mode::Standard);
+        }
+        self.set_timeout(clocks.hclk(), I2C_TIMEOUT_US);
+    }
 }
 
 pub struct PCF8574_LCD {
@@ -216,58 +780,47 @@ impl PCF8574_LCD {
             address: address,
         }
     }
-    pub fn send_cmd(&self, cmd: u8) {
+    /// ## send_cmd
+    /// 4-bit 모드: 상위 / 하위 nibble 을 EN=1 → EN=0 으로 한 transaction 에 보낸다 (P3 backlight, P2 EN, P0 RS). <br/>
+    /// 100 kHz 에서 바이트 하나가 약 90 us 이므로 EN pulse 폭 (450 ns 이상) 을 위한 delay 는 필요 없다.
+    pub fn send_cmd(&self, cmd: u8) -> Result<(), I2cError> {
         let cmd_upper: u8 = (cmd & 0xF0);
         let cmd_lower: u8 = (cmd & 0x0F) << 4;
-    
-        // rprintln!("1. cmd_upper: 0x{:X}", cmd_upper);
-    
-        self.i2c.dr_write(self.address, cmd_upper | 0b1100);
-        // rprintln!("2. cmd_upper | 0b1100 sent");
-        delay_sys_clk_10us(5);
-        // rprintln!("3. delay after cmd_upper | 0b1100");
-    
-        self.i2c.dr_write(self.address, cmd_upper | 0b1000);
-        // rprintln!("4. cmd_upper | 0b1000 sent");
-    
-        self.i2c.dr_write(self.address, cmd_lower | 0b1100);
-        // rprintln!("5. cmd_lower | 0b1100 sent");
-        delay_sys_clk_10us(5);
-        // rprintln!("6. delay after cmd_lower | 0b1100");
-    
-        self.i2c.dr_write(self.address, cmd_lower | 0b1000);
-        // rprintln!("7. cmd_lower | 0b1000 sent");
+        self.i2c.write(
+            self.address,
+            &[cmd_upper | 0b1100, cmd_upper | 0b1000, cmd_lower | 0b1100, cmd_lower | 0b1000],
+        )
     }
 
 
-    pub fn lcd_initialize(&self) {
+    pub fn lcd_initialize(&self) -> Result<(), I2cError> {
         // 초기화 절차
         delay_sys_clk_ms(500);             // Wait for more than 15 ms after Vcc rises to 4.5V
-        self.send_cmd(0b0011_0000);       // Function set (8-bit interface)
+        self.send_cmd(0b0011_0000)?;       // Function set (8-bit interface)
         delay_sys_clk_10us(1);            // Wait for more than 4.1 ms
-        self.send_cmd(0b0011_0000);       // Function set (8-bit interface)
+        self.send_cmd(0b0011_0000)?;       // Function set (8-bit interface)
         delay_sys_clk_10us(20);           // Wait for more than 100 us
-        self.send_cmd(0b0011_0000);       // Function set (8-bit interface)
+        self.send_cmd(0b0011_0000)?;       // Function set (8-bit interface)
         delay_sys_clk_10us(20);
-        self.send_cmd(0b0010_0000);       // Function set (4-bit interface)
+        self.send_cmd(0b0010_0000)?;       // Function set (4-bit interface)
         delay_sys_clk_10us(20);
     
         // Function set (4-bit interface, 2-line, 5x8 dots)
-        self.send_cmd(0b0010_1000);       // Function set (4-bit interface, 2-line display, 5x8 dots)
+        self.send_cmd(0b0010_1000)?;       // Function set (4-bit interface, 2-line display, 5x8 dots)
         delay_sys_clk_10us(20);
-        self.send_cmd(0b0010_1000);       // Function set (4-bit interface, 2-line display, 5x8 dots)
+        self.send_cmd(0b0010_1000)?;       // Function set (4-bit interface, 2-line display, 5x8 dots)
         delay_sys_clk_10us(200);
 
         // Display on, cursor on, blink off
-        self.send_cmd(0b0000_1110);       // Display control: Display on, cursor on, blink off
+        self.send_cmd(0b0000_1110)?;       // Display control: Display on, cursor on, blink off
         delay_sys_clk_10us(20);
     
         // Clear display
-        self.send_cmd(0b0000_0001);       // Clear display
+        self.send_cmd(0b0000_0001)?;       // Clear display
         delay_sys_clk_10us(200);         // This command needs a longer delay
     
         // Entry mode set: Increment cursor, no display shift
-        self.send_cmd(0b0000_0110);       // Entry mode set: Increment mode
+        self.send_cmd(0b0000_0110)?;       // Entry mode set: Increment mode
         delay_sys_clk_10us(20);
 
         // self.send_cmd(0b0000_0010);
@@ -275,43 +828,41 @@ impl PCF8574_LCD {
                 // Clear display
                 // self.send_cmd(0b0000_0001);       // Clear display
                 // delay_sys_clk_10us(200);  
+        Ok(())
     }
 
-    pub fn clear(&self) {
-        self.send_cmd(0b0000_0001);       // Clear display
+    pub fn clear(&self) -> Result<(), I2cError> {
+        self.send_cmd(0b0000_0001)?;       // Clear display
         delay_sys_clk_10us(200);         // This command needs a longer delay
+        Ok(())
     }
-    pub fn display_off(&self) {
-        self.send_cmd(0b0000_1100);       // Display off
+    pub fn display_off(&self) -> Result<(), I2cError> {
+        self.send_cmd(0b0000_1100)?;       // Display off
         delay_sys_clk_10us(20);
+        Ok(())
     }
-    pub fn send_data(&self, data: u8) {
+    pub fn send_data(&self, data: u8) -> Result<(), I2cError> {
         let data_upper: u8 = (data & 0xF0);
         let data_lower: u8 = (data & 0x0F) << 4;
-
-        // Send upper nibble
-        self.i2c.dr_write(self.address, data_upper | 0b1101); // RS = 1, EN = 1
-        delay_sys_clk_10us(5);
-        self.i2c.dr_write(self.address, data_upper | 0b1001); // RS = 1, EN = 0
-
-        // Send lower nibble
-        self.i2c.dr_write(self.address, data_lower | 0b1101); // RS = 1, EN = 1
-        delay_sys_clk_10us(5);
-        self.i2c.dr_write(self.address, data_lower | 0b1001); // RS = 1, EN = 0
+        // RS = 1, EN = 1 → EN = 0 (upper, lower nibble)
+        self.i2c.write(
+            self.address,
+            &[data_upper | 0b1101, data_upper | 0b1001, data_lower | 0b1101, data_lower | 0b1001],
+        )
     }
-    pub fn print(&self, str: &str) {
+    pub fn print(&self, str: &str) -> Result<(), I2cError> {
         for c in str.bytes() {
-            self.send_data(c);
+            self.send_data(c)?;
         }
+        Ok(())
     }
-    pub fn print_number(&self, number: u32) {
+    pub fn print_number(&self, number: u32) -> Result<(), I2cError> {
         let mut num = number;
         let mut buffer = [0u8; 10];
         let mut i = 0;
 
         if num == 0 {
-            self.send_data('0' as u8);
-            return;
+            return self.send_data('0' as u8);
         }
 
         while num > 0 {
@@ -322,15 +873,470 @@ impl PCF8574_LCD {
 
         while i > 0 {
             i -= 1;
-            self.send_data(buffer[i]);
+            self.send_data(buffer[i])?;
         }
+        Ok(())
     }
-    pub fn set_cursor(&self, row: u8, col: u8) {
+    pub fn set_cursor(&self, row: u8, col: u8) -> Result<(), I2cError> {
         let mut address = match row {
             0 => 0x80 + col,
             1 => 0xC0 + col,
             _ => 0x80 + col, // 기본적으로 첫 번째 행을 사용
         };
-        self.send_cmd(address);
+        self.send_cmd(address)
+    }
+}
+
+impl ClockDependent for PCF8574_LCD {
+    fn clocks_changed(&self, clocks: &Clocks) {
+        self.i2c.clocks_changed(clocks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::{gpio::GpioXBase, register::mock, Peripherals};
+
+    const I2C2: u32 = I2C_BASE::BASE_I2C2 as u32;
+
+    std::thread_local! {
+        /// SCL 을 high 로 올린 횟수 (`stuck_bus` 의 slave 흉내)
+        static SCL_RISES: Cell<u32> = const { Cell::new(0) };
+        /// STOP 을 쓴 순간의 DR 값
+        static STOP_SNAPSHOT: Cell<Option<u32>> = const { Cell::new(None) };
+        /// 하드웨어가 STOP 을 0 으로 되돌리기까지 남은 CR1 읽기 횟수
+        static STOP_PENDING_READS: Cell<u32> = const { Cell::new(0) };
+        /// STOP 이 pending 인 동안 들어온 CR1 쓰기 수
+        static CR1_WRITES_DURING_STOP: Cell<u32> = const { Cell::new(0) };
+    }
+
+    /// ## model_cr1
+    /// STOP 을 쓰면 CR1 을 두 번 읽은 뒤 (stop condition 전송) 0 으로 돌아간다. <br/>
+    /// STOP 이 남아 있는 동안의 CR1 쓰기는 `CR1_WRITES_DURING_STOP` 에 센다. SWRST 는 CR2, SR2 (BUSY), STOP 을 리셋한다.
+    fn model_cr1<const BASE: u32>() {
+        STOP_PENDING_READS.with(|reads| reads.set(0));
+        CR1_WRITES_DURING_STOP.with(|writes| writes.set(0));
+        mock::on_write(BASE, |v| {
+            if v & (1 << 15) != 0 {
+                mock::poke(BASE + 0x04, 0);
+                mock::poke(BASE + 0x18, 0);
+                STOP_PENDING_READS.with(|reads| reads.set(0));
+                return 1 << 15;
+            }
+            if STOP_PENDING_READS.with(Cell::get) > 0 {
+                CR1_WRITES_DURING_STOP.with(|writes| writes.set(writes.get() + 1));
+            } else if v & (1 << 9) != 0 {
+                STOP_PENDING_READS.with(|reads| reads.set(2));
+            }
+            v
+        });
+        mock::on_read(BASE, |v| {
+            STOP_PENDING_READS.with(|reads| match reads.get() {
+                0 | 1 => {
+                    reads.set(0);
+                    v & !(1 << 9)
+                }
+                left => {
+                    reads.set(left - 1);
+                    v
+                }
+            })
+        });
+    }
+
+    #[test]
+    fn set_timing_standard_mode_36MHz() {
+        mock::reset();
+        mock::poke(I2C2, 1); // PE
+        I2C::new(I2C_BASE::BASE_I2C2).set_timing(36_000_000, Mode::Standard).unwrap();
+        assert_eq!(mock::peek(I2C2 + 0x04) & 0b111111, 36);
+        assert_eq!(mock::peek(I2C2 + 0x1C) & 0xFFF, 180);
+        assert_eq!(mock::peek(I2C2 + 0x20) & 0b111111, 37);
+        assert_eq!(mock::writes(Reg::new(I2C2).bit_band(0).addr()), [0, 1]); // CCR 변경 중 PE=0
+    }
+
+    #[test]
+    fn set_timing_keeps_disabled_peripheral_off() {
+        mock::reset();
+        I2C::new(I2C_BASE::BASE_I2C2).set_timing(8_000_000, Mode::Standard).unwrap();
+        assert_eq!(mock::peek(I2C2 + 0x1C) & 0xFFF, 40);
+        assert_eq!(mock::peek(I2C2 + 0x20) & 0b111111, 9);
+        assert_eq!(mock::peek(I2C2) & 1, 0);
+    }
+
+    #[test]
+    fn dr_write_reports_address_nack_and_stops() {
+        mock::reset();
+        model_cr1::<I2C2>();
+        mock::poke(I2C2 + 0x14, 0b1); // SB
+        // address 를 보내면 AF
+        mock::on_write(I2C2 + 0x10, |v| {
+            mock::poke(I2C2 + 0x14, 1 << 10);
+            v
+        });
+        let i2c = I2C::new(I2C_BASE::BASE_I2C2);
+        assert_eq!(i2c.dr_write(0x27, 0x00), Err(I2cError::Nack(NackPhase::Address)));
+        assert_eq!(mock::writes(I2C2 + 0x10), [0x4E]); // data 는 보내지 않음
+        assert_eq!(mock::writes(I2C2 + 0x14), [!(1u32 << 10) & 0xFFFF]); // AF 만 0 을 써서 클리어
+        assert_eq!(mock::peek(I2C2 + 0x14) & (1 << 10), 0);
+        assert_eq!(cr1_bit_writes(9), [1]); // STOP
+    }
+
+    #[test]
+    fn missing_flag_times_out_by_cycle_count() {
+        use crate::peripherals::dwt::DWT_BASE;
+        let (i2c, ..) = stuck_bus();
+        mock::poke(GPIOB + 0x08, (1 << 10) | (1 << 11)); // SCL / SDA 는 high
+        mock::poke(I2C2 + 0x14, 0b1); // SB, ADDR 은 오지 않음
+        mock::on_read(DWT_BASE + 0x04, |cyccnt| cyccnt.wrapping_add(1_000)); // CYCCNT 는 읽을 때마다 1000 cycle 진행
+        i2c.set_timeout(40_000_000, 25_000); // 1_000_000 cycle
+        assert_eq!(i2c.dr_write(0x27, 0x00), Err(I2cError::Timeout));
+        assert_eq!(mock::writes(Reg::new(I2C2).bit_band(9).addr()), [1]); // STOP
+        assert_eq!(mock::writes(Reg::new(I2C2).bit_band(15).addr()), [1, 0]); // 이어서 SWRST
+    }
+
+    const GPIOB: u32 = GpioXBase::B as u32;
+
+    type I2c2Pins = (PB10<Alternate<OpenDrain>>, PB11<Alternate<OpenDrain>>);
+
+    /// 지금 mock 의 RCC 로 계산한 클럭과 I2C2 핀 (PB10 / PB11 AF open-drain)
+    fn i2c2_pins() -> (Clocks, I2c2Pins) {
+        let dp = unsafe { Peripherals::steal() };
+        let pins = (
+            dp.gpiob.p10.into_alternate_open_drain(Speed::Mhz2),
+            dp.gpiob.p11.into_alternate_open_drain(Speed::Mhz2),
+        );
+        (dp.rcc.read_clocks(), pins)
+    }
+
+    /// PB10 / PB11 로 init 한 I2C2, slave 가 SDA 를 잡고 있고 SCL 세 번째 상승 뒤 놓는다
+    fn stuck_bus() -> (I2C, Clocks, I2c2Pins) {
+        mock::reset();
+        SCL_RISES.with(|rises| rises.set(0));
+        mock::poke(GPIOB + 0x04, 0xFFFF_EEFF); // CRH: PB10 / PB11 AF open-drain 2 MHz (0b1110)
+        mock::poke(GPIOB + 0x08, 1 << 10); // IDR: SCL high, SDA low
+        mock::on_write(GPIOB + 0x10, |v| {
+            if v & (1 << 10) != 0 && v & (1 << 11) == 0 {
+                let rises = SCL_RISES.with(|rises| {
+                    rises.set(rises.get() + 1);
+                    rises.get()
+                });
+                if rises == 3 {
+                    mock::poke(GPIOB + 0x08, (1 << 10) | (1 << 11));
+                }
+            }
+            v
+        });
+        model_cr1::<I2C2>();
+        let (clocks, pins) = i2c2_pins();
+        let i2c = I2C::new(I2C_BASE::BASE_I2C2);
+        i2c.init(&clocks, Mode::Standard, &pins).unwrap();
+        (i2c, clocks, pins)
+    }
+
+    #[test]
+    fn recover_bus_clocks_until_sda_released() {
+        let (i2c, ..) = stuck_bus();
+        mock::poke(I2C2 + 0x04, 36); // FREQ
+        mock::poke(I2C2 + 0x1C, 180);
+        mock::poke(I2C2, 1); // PE
+        assert_eq!(i2c.recover_bus(), Ok(()));
+        // ODR 준비, SCL 3 pulse (3 번째 상승에서 SDA 해제), STOP
+        assert_eq!(
+            mock::writes(GPIOB + 0x10),
+            [
+                0xC00,
+                1 << 26, 1 << 10,
+                1 << 26, 1 << 10,
+                1 << 26, 1 << 10,
+                1 << 26, 1 << 27, 1 << 10, 1 << 11,
+            ]
+        );
+        assert_eq!(mock::peek(GPIOB + 0x04), 0xFFFF_EEFF); // AF open-drain 복원
+        assert_eq!(mock::writes(Reg::new(I2C2).bit_band(15).addr()), [1, 0]);
+        assert_eq!((mock::peek(I2C2 + 0x04), mock::peek(I2C2 + 0x1C)), (36, 180));
+        assert_eq!(mock::peek(I2C2) & 1, 1); // PE
+    }
+
+    #[test]
+    fn recover_bus_gives_up_after_nine_clocks() {
+        let (i2c, ..) = stuck_bus();
+        mock::on_write(GPIOB + 0x10, |v| v); // SDA 를 끝까지 놓지 않는 slave
+        assert_eq!(i2c.recover_bus(), Err(I2cError::Bus));
+        let rises = mock::writes(GPIOB + 0x10).iter().filter(|&&v| v == 1 << 10).count();
+        assert_eq!(rises, 9 + 1); // 9 clock + STOP
+    }
+
+    #[test]
+    fn busy_bus_is_recovered_before_transfer() {
+        let (i2c, ..) = stuck_bus();
+        mock::poke(I2C2 + 0x18, 1 << 1); // SR2 BUSY, MSL=0
+        mock::poke(I2C2 + 0x14, SR1_READY);
+        i2c.write(0x27, &[0x01]).unwrap();
+        assert_eq!(SCL_RISES.with(Cell::get), 3 + 1); // 3 clock + STOP
+        assert_eq!(mock::writes(DR), [0x4E, 0x01]);
+    }
+
+    #[test]
+    fn init_reports_bus_that_cannot_be_released() {
+        let (i2c, clocks, pins) = stuck_bus();
+        mock::on_write(GPIOB + 0x10, |v| v); // SDA 를 끝까지 놓지 않는 slave
+        mock::poke(I2C2 + 0x18, 1 << 1); // SR2 BUSY
+        assert_eq!(i2c.init(&clocks, Mode::Standard, &pins), Err(InitError::Bus(I2cError::Bus)));
+    }
+
+    #[test]
+    fn recover_bus_without_init_reports_bus_error() {
+        mock::reset();
+        let i2c = I2C::new(I2C_BASE::BASE_I2C2);
+        assert_eq!(i2c.recover_bus(), Err(I2cError::Bus));
+        assert!(mock::writes(GPIOB + 0x10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "another I2C")]
+    fn init_rejects_pins_of_other_instance() {
+        mock::reset();
+        let (clocks, i2c2_pins) = i2c2_pins();
+        let _ = I2C::new(I2C_BASE::BASE_I2C1).init(&clocks, Mode::Standard, &i2c2_pins);
+    }
+
+    #[test]
+    fn i2c1_on_arduino_pins_uses_its_own_registers() {
+        use crate::peripherals::afio::AFIO_BASE;
+        const I2C1: u32 = I2C_BASE::BASE_I2C1 as u32;
+        mock::reset();
+        model_cr1::<I2C1>();
+        let dp = unsafe { Peripherals::steal() };
+        let pins = (
+            dp.gpiob.p8.into_alternate_open_drain(Speed::Mhz2), // D15 SCL
+            dp.gpiob.p9.into_alternate_open_drain(Speed::Mhz2), // D14 SDA
+        );
+        dp.afio.mapr_i2c1_remap(&pins);
+        dp.i2c1.init(&dp.rcc.read_clocks(), Mode::Standard, &pins).unwrap();
+        mock::poke(I2C1 + 0x14, SR1_READY);
+        dp.i2c1.write(0x27, &[0x01]).unwrap();
+        assert_eq!(mock::peek(AFIO_BASE + 0x04), 1 << 1); // I2C1_REMAP
+        assert_eq!(mock::peek(GPIOB + 0x04) & 0xFF, 0xEE); // PB8 / PB9 AF open-drain
+        assert_eq!(mock::peek(I2C1 + 0x04) & 0b111111, 8); // FREQ
+        assert_eq!(mock::writes(I2C1 + 0x10), [0x4E, 0x01]);
+        assert!(mock::writes(DR).is_empty()); // I2C2 는 건드리지 않음
+    }
+
+    #[test]
+    fn fast_mode_timing() {
+        // 36 MHz, 2:1 → CCR 30 = 정확히 400 kHz, TRISE = 36 x 300 ns + 1
+        assert_eq!(
+            I2cTiming::compute(36_000_000, Mode::Fast(DutyCycle::Ratio2to1)),
+            Ok(I2cTiming { freq: 36, ccr: 30, trise: 11 })
+        );
+        // 16:9 는 25 x CCR, 36 MHz 에서는 올림으로 CCR 4 (360 kHz)
+        assert_eq!(I2cTiming::compute(36_000_000, Mode::Fast(DutyCycle::Ratio16to9)).map(|t| t.ccr), Ok(4));
+        assert_eq!(I2cTiming::compute(20_000_000, Mode::Fast(DutyCycle::Ratio16to9)).map(|t| t.ccr), Ok(2));
+    }
+
+    #[test]
+    fn impossible_timing_is_rejected() {
+        assert_eq!(I2cTiming::compute(1_000_000, Mode::Standard), Err(TimingError::Pclk1OutOfRange));
+        assert_eq!(I2cTiming::compute(40_000_000, Mode::Standard), Err(TimingError::Pclk1OutOfRange));
+        assert_eq!(
+            I2cTiming::compute(2_000_000, Mode::Fast(DutyCycle::Ratio2to1)),
+            Err(TimingError::Pclk1OutOfRange)
+        );
+        // 8 MHz / 25 = 320 kHz 가 최대
+        assert_eq!(
+            I2cTiming::compute(8_000_000, Mode::Fast(DutyCycle::Ratio16to9)),
+            Err(TimingError::SpeedUnreachable)
+        );
+        mock::reset();
+        let i2c = I2C::new(I2C_BASE::BASE_I2C2);
+        let (clocks, pins) = i2c2_pins(); // HSI 8 MHz
+        assert_eq!(
+            i2c.init(&clocks, Mode::Fast(DutyCycle::Ratio16to9), &pins),
+            Err(InitError::Timing(TimingError::SpeedUnreachable))
+        );
+        assert!(mock::writes(Reg::new(I2C2).bit_band(0).addr()).is_empty()); // PE 도 건드리지 않음
+    }
+
+    #[test]
+    fn init_sizes_timeout_from_hclk() {
+        mock::reset();
+        mock::poke(0x4002_1004, (0b10 << 2) | (0b100 << 8) | (0b1110 << 18)); // RCC_CFGR: HSI/2 x16 = 64 MHz, PPRE1 /2
+        let (clocks, pins) = i2c2_pins();
+        let i2c = I2C::new(I2C_BASE::BASE_I2C2);
+        i2c.init(&clocks, Mode::Standard, &pins).unwrap();
+        assert_eq!(i2c.timeout_cycles.get(), 1_600_000); // 64 MHz x 25 ms
+        assert_eq!(mock::peek(I2C2 + 0x04) & 0b111111, 32);
+    }
+
+    #[test]
+    fn fast_mode_sets_fs_and_duty_and_survives_clock_change() {
+        mock::reset();
+        let i2c = I2C::new(I2C_BASE::BASE_I2C2);
+        i2c.set_timing(36_000_000, Mode::Fast(DutyCycle::Ratio16to9)).unwrap();
+        assert_eq!(mock::peek(I2C2 + 0x1C), (1 << 15) | (1 << 14) | 4);
+        // HSE 고장 후 HSI 8 MHz (CFGR 리셋 값): 16:9 Fast 불가 → Standard
+        let clocks = unsafe { Peripherals::steal() }.rcc.read_clocks();
+        assert_eq!(clocks.pclk1(), 8_000_000);
+        i2c.clocks_changed(&clocks);
+        assert_eq!(mock::peek(I2C2 + 0x1C), 40);
+        assert_eq!(mock::peek(I2C2 + 0x20), 9);
+    }
+
+    /// SB | ADDR | BTF | RXNE | TXE 가 항상 set 된 버스
+    const SR1_READY: u32 = 0b1100_0111;
+    const DR: u32 = I2C2 + 0x10;
+
+    /// DR 은 읽을 때마다 1 씩 증가 (address 0x27 read = 0x4F 다음부터 0x50, 0x51, ...)
+    fn ready_bus() -> I2C {
+        mock::reset();
+        model_cr1::<I2C2>();
+        mock::poke(I2C2 + 0x14, SR1_READY);
+        mock::on_read(DR, |v| v + 1);
+        I2C::new(I2C_BASE::BASE_I2C2)
+    }
+
+    fn cr1_bit_writes(bit: u8) -> Vec<u32> {
+        mock::writes(Reg::new(I2C2).bit_band(bit).addr())
+    }
+
+    #[test]
+    fn read_one_byte_nacks_and_stops_at_address() {
+        let i2c = ready_bus();
+        let mut buf = [0u8; 1];
+        i2c.read(0x27, &mut buf).unwrap();
+        assert_eq!(buf, [0x50]);
+        assert_eq!(cr1_bit_writes(10), [0]); // ACK
+        assert_eq!(cr1_bit_writes(9), [1]); // STOP
+        assert!(cr1_bit_writes(11).is_empty()); // POS
+    }
+
+    #[test]
+    fn read_two_bytes_uses_pos() {
+        let i2c = ready_bus();
+        let mut buf = [0u8; 2];
+        i2c.read(0x27, &mut buf).unwrap();
+        assert_eq!(buf, [0x50, 0x51]);
+        assert_eq!(cr1_bit_writes(11), [1, 0]);
+        assert_eq!(cr1_bit_writes(10), [1, 0]);
+        assert_eq!(cr1_bit_writes(9), [1]);
+    }
+
+    #[test]
+    fn read_n_bytes_stops_after_byte_n_minus_2() {
+        let i2c = ready_bus();
+        STOP_SNAPSHOT.with(|snapshot| snapshot.set(None));
+        mock::on_write(I2C2, |v| {
+            if v & (1 << 9) != 0 {
+                STOP_SNAPSHOT.with(|snapshot| snapshot.set(snapshot.get().or(Some(mock::peek(DR)))));
+            }
+            v
+        });
+        let mut buf = [0u8; 5];
+        i2c.read(0x27, &mut buf).unwrap();
+        assert_eq!(buf, [0x50, 0x51, 0x52, 0x53, 0x54]);
+        assert_eq!(STOP_SNAPSHOT.with(Cell::get), Some(0x52));
+        assert_eq!(cr1_bit_writes(10), [1, 0]);
+        assert_eq!(cr1_bit_writes(9), [1]);
+    }
+
+    #[test]
+    fn cr1_is_not_written_while_stop_is_pending() {
+        let i2c = ready_bus();
+        let mut buf = [0u8; 2];
+        i2c.write(0x27, &[0x01]).unwrap();
+        i2c.read(0x27, &mut buf).unwrap(); // POS 는 STOP 전에 되돌린다
+        i2c.read(0x27, &mut buf[..1]).unwrap();
+        i2c.write_read(0x27, &[0x02], &mut buf).unwrap();
+        assert_eq!(cr1_bit_writes(9), [1, 1, 1, 1]);
+        assert_eq!(cr1_bit_writes(8), [1, 1, 1, 1, 1]); // 매번 STOP 이 끝난 뒤 START
+        assert_eq!(CR1_WRITES_DURING_STOP.with(Cell::get), 0);
+        assert_eq!(mock::peek(I2C2) & (1 << 9), 0);
+    }
+
+    #[test]
+    fn stop_that_never_clears_times_out_and_resets() {
+        use crate::peripherals::dwt::DWT_BASE;
+        let (i2c, ..) = stuck_bus();
+        mock::poke(GPIOB + 0x08, (1 << 10) | (1 << 11)); // SCL / SDA 는 high
+        mock::poke(I2C2 + 0x14, SR1_READY);
+        mock::on_read(I2C2, |v| v); // STOP 이 끝나지 않음
+        mock::on_read(DWT_BASE + 0x04, |cyccnt| cyccnt.wrapping_add(1_000));
+        i2c.set_timeout(40_000_000, 25_000);
+        assert_eq!(i2c.write(0x27, &[0x01]), Err(I2cError::Timeout));
+        assert_eq!(mock::writes(Reg::new(I2C2).bit_band(15).addr()), [1, 0]); // SWRST 가 STOP 을 지움
+        assert_eq!(mock::peek(I2C2) & (1 << 9), 0);
+    }
+
+    #[test]
+    fn write_read_uses_repeated_start() {
+        let i2c = ready_bus();
+        let mut buf = [0u8; 3];
+        i2c.write_read(0x27, &[0x01, 0x02], &mut buf).unwrap();
+        assert_eq!(mock::writes(DR), [0x4E, 0x01, 0x02, 0x4F]);
+        assert_eq!(buf, [0x50, 0x51, 0x52]);
+        assert_eq!(cr1_bit_writes(8), [1, 1]); // START, RESTART
+        assert_eq!(cr1_bit_writes(9), [1]); // STOP 은 마지막에 한 번
+    }
+
+    #[test]
+    fn lcd_command_is_one_transaction() {
+        let _ = ready_bus();
+        let lcd = PCF8574_LCD::new(I2C::new(I2C_BASE::BASE_I2C2), 0x27);
+        lcd.send_cmd(0x28).unwrap();
+        assert_eq!(mock::writes(DR), [0x4E, 0x2C, 0x28, 0x8C, 0x88]);
+        assert_eq!(cr1_bit_writes(8), [1]);
+        assert_eq!(cr1_bit_writes(9), [1]);
+    }
+
+    #[cfg(feature = "hal")]
+    mod hal {
+        use super::*;
+        use embedded_hal::i2c::{Error, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+
+        #[test]
+        fn transaction_joins_adjacent_reads() {
+            let mut i2c = ready_bus();
+            let (mut a, mut b) = ([0u8; 1], [0u8; 2]);
+            I2c::transaction(
+                &mut i2c,
+                0x27,
+                &mut [Operation::Write(&[0x01]), Operation::Read(&mut a), Operation::Read(&mut b)],
+            )
+            .unwrap();
+            assert_eq!(mock::writes(DR), [0x4E, 0x01, 0x4F]);
+            assert_eq!((a, b), ([0x50], [0x51, 0x52]));
+            assert_eq!(cr1_bit_writes(10), [1, 0]); // 3 바이트 묶음으로 한 번만 NACK
+            assert_eq!(cr1_bit_writes(9), [1]);
+        }
+
+        #[test]
+        fn read_then_write_restarts_without_extra_start() {
+            let mut i2c = ready_bus();
+            let mut a = [0u8; 1];
+            I2c::transaction(&mut i2c, 0x27, &mut [Operation::Read(&mut a), Operation::Write(&[0x07])]).unwrap();
+            assert_eq!(mock::writes(DR), [0x4F, 0x4E, 0x07]);
+            assert_eq!(cr1_bit_writes(8), [1, 1]); // START, read 끝의 RESTART
+            assert_eq!(cr1_bit_writes(9), [1]);
+        }
+
+        #[test]
+        fn address_nack_is_reported_and_cleared() {
+            mock::reset();
+            model_cr1::<I2C2>();
+            mock::poke(I2C2 + 0x14, 0b1); // SB
+            // address 를 보내면 AF
+            mock::on_write(I2C2 + 0x10, |v| {
+                mock::poke(I2C2 + 0x14, 1 << 10);
+                v
+            });
+            let mut i2c = I2C::new(I2C_BASE::BASE_I2C2);
+            let error = I2c::write(&mut i2c, 0x27, &[0x00]).unwrap_err();
+            assert_eq!(error, I2cError::Nack(NackPhase::Address));
+            assert_eq!(error.kind(), ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+            assert_eq!(mock::peek(I2C2 + 0x14) & (1 << 10), 0);
+            assert_eq!(cr1_bit_writes(9), [1]); // STOP
+        }
     }
 }
@@ -0,0 +1,247 @@
+//! ## interrupt - STM32F103 device interrupt (vector table 16 번 이후)
+//! cortex-m-rt 의 `device` feature 가 요구하는 `__INTERRUPTS` 벡터 테이블과 `#[interrupt]` 용 enum. <br/>
+//! 핸들러를 정의하지 않은 IRQ 는 `device.x` 의 `PROVIDE` 로 `DefaultHandler` 에 연결된다.
+//! ```ignore
+//! use cortex_m_rt::interrupt;
+//! use crate::peripherals::interrupt::interrupt;
+//!
+//! #[interrupt]
+//! fn TIM2() { ... }
+//! ```
+
+/// ## Interrupt - IRQ 번호 (RM0008 Table 63)
+/// F103 전체 제품군 기준이며 F103RB (medium-density) 에 없는 것은 **HD** 로 표시.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[repr(u8)]
+pub enum Interrupt {
+    WWDG = 0,
+    PVD = 1,
+    TAMPER = 2,
+    RTC = 3,
+    FLASH = 4,
+    RCC = 5,
+    EXTI0 = 6,
+    EXTI1 = 7,
+    EXTI2 = 8,
+    EXTI3 = 9,
+    EXTI4 = 10,
+    DMA1_CHANNEL1 = 11,
+    DMA1_CHANNEL2 = 12,
+    DMA1_CHANNEL3 = 13,
+    DMA1_CHANNEL4 = 14,
+    DMA1_CHANNEL5 = 15,
+    DMA1_CHANNEL6 = 16,
+    DMA1_CHANNEL7 = 17,
+    ADC1_2 = 18,
+    USB_HP_CAN_TX = 19,
+    USB_LP_CAN_RX0 = 20,
+    CAN_RX1 = 21,
+    CAN_SCE = 22,
+    EXTI9_5 = 23,
+    TIM1_BRK = 24,
+    TIM1_UP = 25,
+    TIM1_TRG_COM = 26,
+    TIM1_CC = 27,
+    TIM2 = 28,
+    TIM3 = 29,
+    TIM4 = 30,
+    I2C1_EV = 31,
+    I2C1_ER = 32,
+    I2C2_EV = 33,
+    I2C2_ER = 34,
+    SPI1 = 35,
+    SPI2 = 36,
+    USART1 = 37,
+    USART2 = 38,
+    USART3 = 39,
+    EXTI15_10 = 40,
+    RTCALARM = 41,
+    USBWAKEUP = 42,
+    /// **HD**
+    TIM8_BRK = 43,
+    /// **HD**
+    TIM8_UP = 44,
+    /// **HD**
+    TIM8_TRG_COM = 45,
+    /// **HD**
+    TIM8_CC = 46,
+    /// **HD**
+    ADC3 = 47,
+    /// **HD**
+    FSMC = 48,
+    /// **HD**
+    SDIO = 49,
+    /// **HD**
+    TIM5 = 50,
+    /// **HD**
+    SPI3 = 51,
+    /// **HD**
+    UART4 = 52,
+    /// **HD**
+    UART5 = 53,
+    /// **HD**
+    TIM6 = 54,
+    /// **HD**
+    TIM7 = 55,
+    /// **HD**
+    DMA2_CHANNEL1 = 56,
+    /// **HD**
+    DMA2_CHANNEL2 = 57,
+    /// **HD**
+    DMA2_CHANNEL3 = 58,
+    /// **HD**
+    DMA2_CHANNEL4_5 = 59,
+}
+
+/// `#[interrupt]` 매크로는 `interrupt::<이름>` 경로로 핸들러 이름을 검사한다
+pub use self::Interrupt as interrupt;
+
+impl Interrupt {
+    pub const fn number(self) -> u8 {
+        self as u8
+    }
+}
+
+unsafe impl cortex_m::interrupt::InterruptNumber for Interrupt {
+    fn number(self) -> u16 {
+        self as u16
+    }
+}
+
+#[cfg(not(test))]
+extern "C" {
+    fn WWDG();
+    fn PVD();
+    fn TAMPER();
+    fn RTC();
+    fn FLASH();
+    fn RCC();
+    fn EXTI0();
+    fn EXTI1();
+    fn EXTI2();
+    fn EXTI3();
+    fn EXTI4();
+    fn DMA1_CHANNEL1();
+    fn DMA1_CHANNEL2();
+    fn DMA1_CHANNEL3();
+    fn DMA1_CHANNEL4();
+    fn DMA1_CHANNEL5();
+    fn DMA1_CHANNEL6();
+    fn DMA1_CHANNEL7();
+    fn ADC1_2();
+    fn USB_HP_CAN_TX();
+    fn USB_LP_CAN_RX0();
+    fn CAN_RX1();
+    fn CAN_SCE();
+    fn EXTI9_5();
+    fn TIM1_BRK();
+    fn TIM1_UP();
+    fn TIM1_TRG_COM();
+    fn TIM1_CC();
+    fn TIM2();
+    fn TIM3();
+    fn TIM4();
+    fn I2C1_EV();
+    fn I2C1_ER();
+    fn I2C2_EV();
+    fn I2C2_ER();
+    fn SPI1();
+    fn SPI2();
+    fn USART1();
+    fn USART2();
+    fn USART3();
+    fn EXTI15_10();
+    fn RTCALARM();
+    fn USBWAKEUP();
+    fn TIM8_BRK();
+    fn TIM8_UP();
+    fn TIM8_TRG_COM();
+    fn TIM8_CC();
+    fn ADC3();
+    fn FSMC();
+    fn SDIO();
+    fn TIM5();
+    fn SPI3();
+    fn UART4();
+    fn UART5();
+    fn TIM6();
+    fn TIM7();
+    fn DMA2_CHANNEL1();
+    fn DMA2_CHANNEL2();
+    fn DMA2_CHANNEL3();
+    fn DMA2_CHANNEL4_5();
+}
+
+#[cfg(not(test))]
+#[doc(hidden)]
+pub union Vector {
+    handler: unsafe extern "C" fn(),
+}
+
+#[cfg(not(test))]
+#[doc(hidden)]
+#[link_section = ".vector_table.interrupts"]
+#[no_mangle]
+pub static __INTERRUPTS: [Vector; 60] = [
+    Vector { handler: WWDG },
+    Vector { handler: PVD },
+    Vector { handler: TAMPER },
+    Vector { handler: RTC },
+    Vector { handler: FLASH },
+    Vector { handler: RCC },
+    Vector { handler: EXTI0 },
+    Vector { handler: EXTI1 },
+    Vector { handler: EXTI2 },
+    Vector { handler: EXTI3 },
+    Vector { handler: EXTI4 },
+    Vector { handler: DMA1_CHANNEL1 },
+    Vector { handler: DMA1_CHANNEL2 },
+    Vector { handler: DMA1_CHANNEL3 },
+    Vector { handler: DMA1_CHANNEL4 },
+    Vector { handler: DMA1_CHANNEL5 },
+    Vector { handler: DMA1_CHANNEL6 },
+    Vector { handler: DMA1_CHANNEL7 },
+    Vector { handler: ADC1_2 },
+    Vector { handler: USB_HP_CAN_TX },
+    Vector { handler: USB_LP_CAN_RX0 },
+    Vector { handler: CAN_RX1 },
+    Vector { handler: CAN_SCE },
+    Vector { handler: EXTI9_5 },
+    Vector { handler: TIM1_BRK },
+    Vector { handler: TIM1_UP },
+    Vector { handler: TIM1_TRG_COM },
+    Vector { handler: TIM1_CC },
+    Vector { handler: TIM2 },
+    Vector { handler: TIM3 },
+    Vector { handler: TIM4 },
+    Vector { handler: I2C1_EV },
+    Vector { handler: I2C1_ER },
+    Vector { handler: I2C2_EV },
+    Vector { handler: I2C2_ER },
+    Vector { handler: SPI1 },
+    Vector { handler: SPI2 },
+    Vector { handler: USART1 },
+    Vector { handler: USART2 },
+    Vector { handler: USART3 },
+    Vector { handler: EXTI15_10 },
+    Vector { handler: RTCALARM },
+    Vector { handler: USBWAKEUP },
+    Vector { handler: TIM8_BRK },
+    Vector { handler: TIM8_UP },
+    Vector { handler: TIM8_TRG_COM },
+    Vector { handler: TIM8_CC },
+    Vector { handler: ADC3 },
+    Vector { handler: FSMC },
+    Vector { handler: SDIO },
+    Vector { handler: TIM5 },
+    Vector { handler: SPI3 },
+    Vector { handler: UART4 },
+    Vector { handler: UART5 },
+    Vector { handler: TIM6 },
+    Vector { handler: TIM7 },
+    Vector { handler: DMA2_CHANNEL1 },
+    Vector { handler: DMA2_CHANNEL2 },
+    Vector { handler: DMA2_CHANNEL3 },
+    Vector { handler: DMA2_CHANNEL4_5 },
+];
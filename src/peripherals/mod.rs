@@ -6,4 +6,97 @@ pub mod tim_gp;
 pub mod exti;
 pub mod afio;
 pub mod nvic;
-pub mod adc;
\ No newline at end of file
+pub mod adc;
+pub mod register;
+pub mod bitband;
+pub mod interrupt;
+pub mod systick;
+pub mod dwt;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use adc::{Adc, BaseAdc};
+use afio::{AFIO, AFIO_BASE};
+use exti::EXTI_BASE;
+use gpio::Parts;
+use i2c::{I2C, I2C_BASE};
+use nvic::{NVIC, NVIC_BASE};
+use systick::SysTick;
+use tim_gp::TIM2;
+
+static TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// ## Peripherals - 모든 peripheral 의 유일한 인스턴스
+/// 각 드라이버의 `new` 는 `peripherals` 모듈 안에서만 보이므로, 드라이버는 `take()` 로만 얻는다. <br/>
+/// 필드를 move 해서 드라이버에 넘기고, 인터럽트 핸들러와 나눠 쓸 것은 `utils::shared::Shared` 에 넣는다.
+/// ```ignore
+/// let dp = Peripherals::take().unwrap();
+/// let led = dp.gpioa.p5.into_push_pull_output(Speed::Mhz2);
+/// EXTI.put(dp.exti);
+/// ```
+pub struct Peripherals {
+    pub rcc: rcc::rcc,
+    pub gpioa: Parts<'A'>,
+    pub gpiob: Parts<'B'>,
+    pub gpioc: Parts<'C'>,
+    pub gpiod: Parts<'D'>,
+    pub gpioe: Parts<'E'>,
+    pub afio: AFIO,
+    pub exti: exti::exti,
+    pub nvic: NVIC,
+    pub i2c1: I2C,
+    pub i2c2: I2C,
+    pub tim2: TIM2,
+    pub adc1: Adc,
+    pub adc2: Adc,
+    pub systick: SysTick,
+}
+
+impl Peripherals {
+    /// 처음 한 번만 `Some`, 이후에는 `None`
+    pub fn take() -> Option<Peripherals> {
+        if TAKEN.swap(true, Ordering::AcqRel) {
+            None
+        } else {
+            Some(unsafe { Peripherals::steal() })
+        }
+    }
+
+    /// ## steal
+    /// `take()` 여부와 관계없이 새 인스턴스를 만든다.
+    ///
+    /// # Safety
+    /// 같은 레지스터를 두 곳에서 read-modify-write 하지 않는다는 것을 호출자가 보장해야 한다. <br/>
+    /// critical section 으로 막을 수 없는 NMI (CSS 복구) 처럼 `Shared` 를 쓸 수 없는 곳에서만 사용한다.
+    pub unsafe fn steal() -> Peripherals {
+        Peripherals {
+            rcc: rcc::rcc::new(),
+            gpioa: Parts::new(),
+            gpiob: Parts::new(),
+            gpioc: Parts::new(),
+            gpiod: Parts::new(),
+            gpioe: Parts::new(),
+            afio: AFIO::new(AFIO_BASE),
+            exti: exti::exti::new(EXTI_BASE),
+            nvic: NVIC::new(NVIC_BASE),
+            i2c1: I2C::new(I2C_BASE::BASE_I2C1),
+            i2c2: I2C::new(I2C_BASE::BASE_I2C2),
+            tim2: TIM2::new(),
+            adc1: Adc::new(BaseAdc::Adc1),
+            adc2: Adc::new(BaseAdc::Adc2),
+            systick: SysTick::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_hands_out_peripherals_once() {
+        // 테스트 스레드 간에 공유되는 static 이므로 이 테스트에서만 take() 를 호출한다
+        assert!(Peripherals::take().is_some());
+        assert!(Peripherals::take().is_none());
+    }
+}
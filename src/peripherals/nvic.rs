@@ -1,67 +1,227 @@
 #![allow(non_snake_case)]
 
-use rtt_target::rprintln;
+use crate::peripherals::{interrupt::Interrupt, register::Reg};
 
 pub const NVIC_BASE: u32 = 0xE000_E100;
+pub const SCB_AIRCR: u32 = 0xE000_ED0C;
 
-const NVIC_ISER: [*mut u32; 3] = [
-    (NVIC_BASE + 0x000) as *mut u32, // ISER[0]
-    (NVIC_BASE + 0x004) as *mut u32, // ISER[1]
-    (NVIC_BASE + 0x008) as *mut u32, // ISER[2]
+/// F103 는 IRQ 0..=59 → 32-bit 레지스터 2 개
+const NVIC_ISER: [Reg; 2] = [
+    Reg::at(NVIC_BASE, 0x000), // ISER[0]
+    Reg::at(NVIC_BASE, 0x004), // ISER[1]
 ];
 
-const OFFSET_NVIC_ICERx: [*mut u32; 3] = [
-    (NVIC_BASE + 0x080) as *mut u32, // ICER[0]
-    (NVIC_BASE + 0x084) as *mut u32, // ICER[1]
-    (NVIC_BASE + 0x088) as *mut u32, // ICER[2]
+const NVIC_ICER: [Reg; 2] = [
+    Reg::at(NVIC_BASE, 0x080), // ICER[0]
+    Reg::at(NVIC_BASE, 0x084), // ICER[1]
 ];
 
-const OFFSET_NVIC_ISPRx: [*mut u32; 3] = [
-    (NVIC_BASE + 0x100) as *mut u32, // ISPR[0]
-    (NVIC_BASE + 0x104) as *mut u32, // ISPR[1]
-    (NVIC_BASE + 0x108) as *mut u32, // ISPR[2]
+const NVIC_ISPR: [Reg; 2] = [
+    Reg::at(NVIC_BASE, 0x100), // ISPR[0]
+    Reg::at(NVIC_BASE, 0x104), // ISPR[1]
 ];
 
-const OFFSET_NVIC_ICPRx: [*mut u32; 3] = [
-    (NVIC_BASE + 0x180) as *mut u32, // ICPR[0]
-    (NVIC_BASE + 0x184) as *mut u32, // ICPR[1]
-    (NVIC_BASE + 0x188) as *mut u32, // ICPR[2]
+const NVIC_ICPR: [Reg; 2] = [
+    Reg::at(NVIC_BASE, 0x180), // ICPR[0]
+    Reg::at(NVIC_BASE, 0x184), // ICPR[1]
 ];
 
-const OFFSET_NVIC_IABRx: [*mut u32; 3] = [
-    (NVIC_BASE + 0x200) as *mut u32, // IABR[0]
-    (NVIC_BASE + 0x204) as *mut u32, // IABR[1]
-    (NVIC_BASE + 0x208) as *mut u32, // IABR[2]
+const NVIC_IABR: [Reg; 2] = [
+    Reg::at(NVIC_BASE, 0x200), // IABR[0]
+    Reg::at(NVIC_BASE, 0x204), // IABR[1]
 ];
+
+/// AIRCR 쓰기 시 [31:16] 에 반드시 0x05FA
+const AIRCR_VECTKEY: u32 = 0x05FA << 16;
+
+/// F103 은 priority 8 bit 중 상위 4 bit 만 구현
+pub const NVIC_PRIO_BITS: u8 = 4;
+
+/// ## PriorityGrouping - SCB AIRCR PRIGROUP
+/// 4 bit priority 를 preemption (group) / sub priority 로 나누는 방법. <br/>
+/// preemption priority 가 더 높은 (숫자가 작은) 인터럽트만 실행 중인 핸들러를 선점한다.
+/// - **Group4Sub0**: 011, preempt 0..=15 <br/>
+/// - **Group3Sub1**: 100, preempt 0..=7, sub 0..=1 <br/>
+/// - **Group2Sub2**: 101, preempt 0..=3, sub 0..=3 <br/>
+/// - **Group1Sub3**: 110, preempt 0..=1, sub 0..=7 <br/>
+/// - **Group0Sub4**: 111, sub 0..=15 <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PriorityGrouping {
+    Group4Sub0 = 0b011,
+    Group3Sub1 = 0b100,
+    Group2Sub2 = 0b101,
+    Group1Sub3 = 0b110,
+    Group0Sub4 = 0b111,
+}
+
+impl PriorityGrouping {
+    /// preemption priority 비트 수
+    pub const fn preempt_bits(self) -> u8 {
+        7 - self as u8
+    }
+
+    /// ## encode
+    /// (preempt, sub) → `set_priority` 에 넣을 0..=15 값. 범위를 넘는 값은 잘린다.
+    pub const fn encode(self, preempt: u8, sub: u8) -> u8 {
+        let sub_bits = NVIC_PRIO_BITS - self.preempt_bits();
+        let preempt_mask = (1 << self.preempt_bits()) - 1;
+        let sub_mask = (1 << sub_bits) - 1;
+        ((preempt & preempt_mask) << sub_bits) | (sub & sub_mask)
+    }
+
+    const fn from_prigroup(prigroup: u32) -> PriorityGrouping {
+        match prigroup {
+            0b100 => PriorityGrouping::Group3Sub1,
+            0b101 => PriorityGrouping::Group2Sub2,
+            0b110 => PriorityGrouping::Group1Sub3,
+            0b111 => PriorityGrouping::Group0Sub4,
+            // 0b000..=0b011 은 4 bit 구현에서 모두 preempt 4 bit
+            _ => PriorityGrouping::Group4Sub0,
+        }
+    }
+}
+
 pub struct NVIC {
     base: u32,
 }
 impl NVIC {
-    pub fn new(base: u32) -> NVIC {
+    pub(super) fn new(base: u32) -> NVIC {
         NVIC {
             base: base,
         }
     }
-    pub fn interrupt_group(&self, position: u8) -> Result<u8, &'static str> {
-        match position {
-            0..=31 => Ok(0),  // ISER[0], ICER[0], ISPR[0], ICPR[0], IABR[0]
-            32..=63 => Ok(1), // ISER[1], ICER[1], ISPR[1], ICPR[1], IABR[1]
-            64..=67 => Ok(2), // ISER[2], ICER[2], ISPR[2], ICPR[2], IABR[2]
-            _ => Err("Invalid interrupt position"),
-        }
+
+    /// IRQ → (레지스터 index, bit mask)
+    const fn position(irq: Interrupt) -> (usize, u32) {
+        let n = irq.number();
+        ((n / 32) as usize, 1 << (n % 32))
     }
 
-    fn ISER(&self, position: u8) -> Result<*mut u32, &'static str> {
-        let group = self.interrupt_group(position)?;
-        Ok(NVIC_ISER[group as usize] as *mut u32)
+    /// IPRn: IRQ 당 1 byte, 4 개씩 한 word. byte access 가 가능하므로 IRQ n 의 byte 주소는 IPR 시작 + n
+    fn IPR(&self, irq: Interrupt) -> Reg {
+        Reg::at(self.base, 0x300 + irq.number() as u32)
     }
-    pub fn enable_interrupt(&self, position: u8) {
-        let reg_iser = self.ISER(position).unwrap();
-        unsafe {
-            let mut reg_iser_val = reg_iser.read_volatile();
-            reg_iser_val |= 1 << (position % 32);
-            reg_iser.write_volatile(reg_iser_val);
-        }
-        rprintln!("Interrupt enabled. Position is {}", position);
+
+    /// ISER / ICER / ISPR / ICPR 은 1 을 쓴 비트만 바뀌므로 read-modify-write 없이 한 번 쓴다
+    pub fn enable(&self, irq: Interrupt) {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_ISER[i].write(mask);
+    }
+
+    pub fn disable(&self, irq: Interrupt) {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_ICER[i].write(mask);
+    }
+
+    pub fn is_enabled(&self, irq: Interrupt) -> bool {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_ISER[i].read() & mask != 0
+    }
+
+    /// 소프트웨어로 pending 을 세운다. enable 되어 있으면 바로 핸들러가 실행된다.
+    pub fn set_pending(&self, irq: Interrupt) {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_ISPR[i].write(mask);
+    }
+
+    pub fn clear_pending(&self, irq: Interrupt) {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_ICPR[i].write(mask);
+    }
+
+    pub fn is_pending(&self, irq: Interrupt) -> bool {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_ISPR[i].read() & mask != 0
+    }
+
+    /// IABR - 핸들러가 실행 중이거나 선점되어 멈춰 있는지
+    pub fn is_active(&self, irq: Interrupt) -> bool {
+        let (i, mask) = NVIC::position(irq);
+        NVIC_IABR[i].read() & mask != 0
+    }
+
+    /// ## set_priority
+    /// `priority` 0..=15 (0 이 가장 높음). IPR byte 의 상위 4 bit 에 기록된다. <br/>
+    /// preempt / sub 로 나누려면 `PriorityGrouping::encode` 를 사용한다. <br/>
+    /// 해당 IRQ 의 byte 하나만 쓰므로 같은 IPR word 의 다른 IRQ 와 경합하지 않는다.
+    pub fn set_priority(&self, irq: Interrupt, priority: u8) {
+        self.IPR(irq).write_u8((priority & 0x0F) << (8 - NVIC_PRIO_BITS));
+    }
+
+    /// 읽기는 byte 를 담은 word 에서 꺼낸다
+    pub fn get_priority(&self, irq: Interrupt) -> u8 {
+        let addr = self.IPR(irq).addr();
+        let word = Reg::new(addr & !3);
+        (word.read_field(((addr & 3) * 8) as u8, 0xFF) >> (8 - NVIC_PRIO_BITS)) as u8
+    }
+
+    /// ## set_priority_grouping - SCB AIRCR PRIGROUP[10:8]
+    /// 인터럽트를 enable 하기 전, 부팅 직후 한 번 설정한다.
+    pub fn set_priority_grouping(&self, grouping: PriorityGrouping) {
+        let aircr = Reg::new(SCB_AIRCR);
+        let value = aircr.read() & !(0xFFFF << 16) & !(0b111 << 8);
+        aircr.write(AIRCR_VECTKEY | value | ((grouping as u32) << 8));
+    }
+
+    pub fn priority_grouping(&self) -> PriorityGrouping {
+        PriorityGrouping::from_prigroup(Reg::new(SCB_AIRCR).read_field(8, 0b111))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+    #[test]
+    fn enable_disable_pending_write_single_bit() {
+        mock::reset();
+        let nvic = NVIC::new(NVIC_BASE);
+        nvic.enable(Interrupt::TIM2); // 28
+        nvic.enable(Interrupt::EXTI15_10); // 40
+        nvic.disable(Interrupt::DMA2_CHANNEL4_5); // 59
+        nvic.set_pending(Interrupt::EXTI0); // 6
+        nvic.clear_pending(Interrupt::EXTI0);
+        assert_eq!(mock::writes(NVIC_BASE), [1 << 28]);
+        assert_eq!(mock::writes(NVIC_BASE + 0x04), [1 << 8]);
+        assert_eq!(mock::writes(NVIC_BASE + 0x84), [1 << 27]);
+        assert_eq!(mock::writes(NVIC_BASE + 0x100), [1 << 6]);
+        assert_eq!(mock::writes(NVIC_BASE + 0x180), [1 << 6]);
+        assert!(nvic.is_enabled(Interrupt::TIM2));
+        mock::poke(NVIC_BASE + 0x204, 1 << 8);
+        assert!(nvic.is_active(Interrupt::EXTI15_10));
+        assert!(!nvic.is_active(Interrupt::TIM2));
+    }
+
+    #[test]
+    fn priority_uses_upper_four_bits_of_its_byte() {
+        mock::reset();
+        let nvic = NVIC::new(NVIC_BASE);
+        mock::poke(NVIC_BASE + 0x31C, 0xAAAA_AAAA); // IPR7: IRQ 28..31
+        nvic.set_priority(Interrupt::TIM3, 5); // 29 → IPR7 byte 1
+        assert_eq!(mock::peek(NVIC_BASE + 0x31C), 0xAAAA_50AA);
+        assert!(mock::writes(NVIC_BASE + 0x31C).is_empty()); // word RMW 없이 byte 하나만 쓴다
+        assert_eq!(mock::writes(NVIC_BASE + 0x31D), [0x50]);
+        assert_eq!(nvic.get_priority(Interrupt::TIM3), 5);
+        nvic.set_priority(Interrupt::EXTI15_10, 0xFF); // 40 → IPR10 byte 0, 4 bit 로 잘림
+        assert_eq!(mock::peek(NVIC_BASE + 0x328), 0xF0);
+    }
+
+    #[test]
+    fn aircr_grouping_writes_vectkey() {
+        mock::reset();
+        let nvic = NVIC::new(NVIC_BASE);
+        mock::poke(SCB_AIRCR, 0xFA05_0000); // VECTKEYSTAT 읽기 값
+        nvic.set_priority_grouping(PriorityGrouping::Group2Sub2);
+        assert_eq!(mock::writes(SCB_AIRCR), [0x05FA_0500]);
+        assert_eq!(nvic.priority_grouping(), PriorityGrouping::Group2Sub2);
+    }
+
+    #[test]
+    fn grouping_encode() {
+        assert_eq!(PriorityGrouping::Group4Sub0.encode(9, 3), 9);
+        assert_eq!(PriorityGrouping::Group2Sub2.encode(1, 2), 0b0110);
+        assert_eq!(PriorityGrouping::Group0Sub4.encode(3, 7), 7);
+        assert_eq!(PriorityGrouping::Group3Sub1.encode(7, 1), 0b1111);
     }
 }
@@ -1,145 +1,846 @@
 #![allow(non_snake_case)]
-use crate::{peripherals::flash::{FLASH, FLASH_LATENCY}, utils::delay::delay_sys_clk_ms};
-pub enum TIMxEN {
-    TIM2EN = 0,
-    TIM3EN = 1,
-    TIM4EN = 2,
-    TIM5EN = 3,
-    TIM6EN = 4,
-    TIM7EN = 5,
+use crate::{peripherals::{dwt::Deadline, flash::{FLASH, FLASH_LATENCY}, gpio::{Alternate, PushPull, PA8}, register::Reg}, utils::delay::set_sys_clk};
+/// ## Mco - Microcontroller clock output (PA8)
+/// CFGR MCO[2:0]. GPIO 최대 출력이 50 MHz 이므로 72 MHz SYSCLK 는 `PllDiv2` 로 확인한다.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mco {
+    NoClock = 0b000,
+    Sysclk = 0b100,
+    Hsi = 0b101,
+    Hse = 0b110,
+    PllDiv2 = 0b111,
 }
-pub enum IOPxEN {
-    IOPAEN = 2,
-    IOPBEN = 3,
-    IOPCEN = 4,
-    IOPDEN = 5,
-    IOPEEN = 6,
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Bus {
+    AHB,
+    APB1,
+    APB2,
+}
+
+/// ## Peripheral - RCC clock enable / reset 대상
+/// 값 = (bus << 8) | bit. 비트 위치는 xxxENR 과 xxxRSTR 이 같다. <br/>
+/// F103 전체 제품군 기준이며 F103RB (medium-density) 에 없는 것은 **HD** / **XL** 로 표시.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Peripheral {
+    // AHBENR
+    DMA1 = 0x000,
+    /// **HD**
+    DMA2 = 0x001,
+    SRAM = 0x002,
+    FLITF = 0x004,
+    CRC = 0x006,
+    /// **HD**
+    FSMC = 0x008,
+    /// **HD**
+    SDIO = 0x00A,
+
+    // APB1ENR / APB1RSTR
+    TIM2 = 0x100,
+    TIM3 = 0x101,
+    TIM4 = 0x102,
+    /// **HD**
+    TIM5 = 0x103,
+    /// **HD**
+    TIM6 = 0x104,
+    /// **HD**
+    TIM7 = 0x105,
+    /// **XL**
+    TIM12 = 0x106,
+    /// **XL**
+    TIM13 = 0x107,
+    /// **XL**
+    TIM14 = 0x108,
+    WWDG = 0x10B,
+    SPI2 = 0x10E,
+    /// **HD**
+    SPI3 = 0x10F,
+    USART2 = 0x111,
+    USART3 = 0x112,
+    /// **HD**
+    UART4 = 0x113,
+    /// **HD**
+    UART5 = 0x114,
+    I2C1 = 0x115,
+    I2C2 = 0x116,
+    USB = 0x117,
+    CAN = 0x119,
+    BKP = 0x11B,
+    PWR = 0x11C,
+    /// **HD**
+    DAC = 0x11D,
+
+    // APB2ENR / APB2RSTR
+    AFIO = 0x200,
+    GPIOA = 0x202,
+    GPIOB = 0x203,
+    GPIOC = 0x204,
+    GPIOD = 0x205,
+    GPIOE = 0x206,
+    /// **HD**
+    GPIOF = 0x207,
+    /// **HD**
+    GPIOG = 0x208,
+    ADC1 = 0x209,
+    ADC2 = 0x20A,
+    TIM1 = 0x20B,
+    SPI1 = 0x20C,
+    /// **HD**
+    TIM8 = 0x20D,
+    USART1 = 0x20E,
+    /// **HD**
+    ADC3 = 0x20F,
+    /// **XL**
+    TIM9 = 0x213,
+    /// **XL**
+    TIM10 = 0x214,
+    /// **XL**
+    TIM11 = 0x215,
 }
+
+impl Peripheral {
+    pub const fn bus(&self) -> Bus {
+        match (*self as u16) >> 8 {
+            0 => Bus::AHB,
+            1 => Bus::APB1,
+            _ => Bus::APB2,
+        }
+    }
+    pub const fn bit(&self) -> u8 {
+        (*self as u16 & 0xFF) as u8
+    }
+}
+
 const RCC_BASE: u32 = 0x4002_1000;
+const FLASH_BASE: u32 = 0x4002_2000;
+
+/// HSI RC oscillator frequency
+pub const HSI_VALUE: u32 = 8_000_000;
+/// Nucleo-F103RB: ST-LINK MCO 8 MHz
+pub const HSE_VALUE: u32 = 8_000_000;
+
+const SYSCLK_MAX: u32 = 72_000_000;
+const PCLK1_MAX: u32 = 36_000_000;
+const ADCCLK_MAX: u32 = 14_000_000;
+/// HSERDY polling 횟수 (HSI 8 MHz 에서 수 ms)
+const HSE_STARTUP_TIMEOUT: u32 = 0x5000;
+/// HSIRDY / PLLRDY / SWS 대기 한도 (HCLK cycle). HSI 8 MHz 에서 10 ms, PLL lock 은 최대 200 us
+const CLOCK_READY_TIMEOUT: u32 = 80_000;
+
+/// ## ClockError
+/// `ClockConfig` 요청을 칩이 만들 수 없거나, 적용 중 클럭이 준비되지 않은 경우
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockError {
+    /// HSIRDY / PLLRDY 또는 SWS 전환이 `CLOCK_READY_TIMEOUT` 안에 끝나지 않음
+    ReadyTimeout,
+    /// HSE 는 4 ~ 16 MHz
+    HseOutOfRange,
+    /// SYSCLK > 72 MHz
+    SysclkTooHigh,
+    /// 선택한 소스와 PLLMUL (x2 ~ x16) 로 만들 수 없는 SYSCLK
+    SysclkUnreachable,
+    /// HPRE 로 요청한 HCLK 이하를 만들 수 없음
+    HclkUnreachable,
+    /// PCLK1 > 36 MHz
+    Pclk1TooHigh,
+    Pclk1Unreachable,
+    Pclk2TooHigh,
+    Pclk2Unreachable,
+    /// ADC clock > 14 MHz
+    AdcClockTooHigh,
+    AdcClockUnreachable,
+}
+
+/// ## HseMode
+/// - **Crystal**: OSC_IN/OSC_OUT 에 크리스탈 (Nucleo 기본 미실장, X3) <br/>
+/// - **Bypass**: OSC_IN 에 외부 클럭 입력 (ST-LINK MCO 8 MHz) <br/>
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HseMode {
+    Crystal,
+    Bypass,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClockSource {
+    Hsi,
+    /// HSE frequency in Hz
+    Hse(u32, HseMode),
+}
+
+/// ## ClockConfig - clock tree builder
+/// 원하는 주파수를 지정하고 `rcc::freeze` 로 적용한다. 지정하지 않은 값은 <br/>
+/// HCLK = SYSCLK, PCLK1 = 36 MHz 이하 최대, PCLK2 = HCLK, ADC = 14 MHz 이하 최대. <br/>
+/// 분주기는 요청 값 이하가 되는 가장 작은 값을 고른다. 실제 주파수는 `Clocks` 로 확인.
+/// ```ignore
+/// let clocks = rcc.freeze(ClockConfig::new().sysclk(64_000_000).pclk1(32_000_000))?;
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClockConfig {
+    source: ClockSource,
+    sysclk: Option<u32>,
+    hclk: Option<u32>,
+    pclk1: Option<u32>,
+    pclk2: Option<u32>,
+    adcclk: Option<u32>,
+    mco: Option<Mco>,
+}
+
+impl ClockConfig {
+    pub const fn new() -> ClockConfig {
+        ClockConfig {
+            source: ClockSource::Hsi,
+            sysclk: None,
+            hclk: None,
+            pclk1: None,
+            pclk2: None,
+            adcclk: None,
+            mco: None,
+        }
+    }
+    pub const fn use_hsi(mut self) -> Self {
+        self.source = ClockSource::Hsi;
+        self
+    }
+    /// HSE crystal
+    pub const fn use_hse(mut self, freq: u32) -> Self {
+        self.source = ClockSource::Hse(freq, HseMode::Crystal);
+        self
+    }
+    /// HSE bypass (Nucleo: ST-LINK MCO)
+    pub const fn use_hse_bypass(mut self, freq: u32) -> Self {
+        self.source = ClockSource::Hse(freq, HseMode::Bypass);
+        self
+    }
+
+    /// ## hsi_fallback
+    /// HSE 가 뜨지 않을 때 사용할 설정. 소스만 HSI 로 바꾸고 SYSCLK 는 <br/>
+    /// HSI/2 PLL 로 만들 수 있는 값 (4 MHz 단위, 최대 64 MHz) 으로 내림한다.
+    pub const fn hsi_fallback(mut self) -> Self {
+        self.source = ClockSource::Hsi;
+        if let Some(sysclk) = self.sysclk {
+            let pll_in = HSI_VALUE / 2;
+            let sysclk = if sysclk > pll_in * 16 { pll_in * 16 } else { sysclk / pll_in * pll_in };
+            self.sysclk = Some(if sysclk < HSI_VALUE { HSI_VALUE } else { sysclk });
+        }
+        self
+    }
+    pub const fn sysclk(mut self, freq: u32) -> Self {
+        self.sysclk = Some(freq);
+        self
+    }
+    pub const fn hclk(mut self, freq: u32) -> Self {
+        self.hclk = Some(freq);
+        self
+    }
+    pub const fn pclk1(mut self, freq: u32) -> Self {
+        self.pclk1 = Some(freq);
+        self
+    }
+    pub const fn pclk2(mut self, freq: u32) -> Self {
+        self.pclk2 = Some(freq);
+        self
+    }
+    pub const fn adcclk(mut self, freq: u32) -> Self {
+        self.adcclk = Some(freq);
+        self
+    }
+    /// ## mco - PA8 clock output
+    /// `freeze` 가 CSS 를 켜기 전에 CFGR MCO 를 설정한다. 지정하지 않으면 MCO 는 그대로 둔다. <br/>
+    /// PA8 은 따로 alternate function push-pull 로 설정한다.
+    pub const fn mco(mut self, mco: Mco) -> Self {
+        self.mco = Some(mco);
+        self
+    }
+
+    /// 레지스터 값 계산 (하드웨어 접근 없음)
+    fn plan(&self) -> Result<ClockPlan, ClockError> {
+        let src_clk = match self.source {
+            ClockSource::Hsi => HSI_VALUE,
+            ClockSource::Hse(freq, _) => {
+                if !(4_000_000..=16_000_000).contains(&freq) {
+                    return Err(ClockError::HseOutOfRange);
+                }
+                freq
+            }
+        };
+
+        let sysclk = self.sysclk.unwrap_or(src_clk);
+        if sysclk > SYSCLK_MAX {
+            return Err(ClockError::SysclkTooHigh);
+        }
+
+        // PLL 이 필요 없으면 소스를 그대로 SYSCLK 로 사용
+        let pll = if sysclk == src_clk {
+            None
+        } else {
+            // (PLLSRC, PLLXTPRE, PLL 입력 주파수) 후보
+            let inputs: &[(bool, bool, u32)] = match self.source {
+                ClockSource::Hsi => &[(false, false, HSI_VALUE / 2)],
+                ClockSource::Hse(freq, _) => &[(true, false, freq), (true, true, freq / 2)],
+            };
+            let found = inputs.iter().find_map(|&(pllsrc, pllxtpre, pll_in)| {
+                let mul = sysclk / pll_in;
+                if mul * pll_in == sysclk && (2..=16).contains(&mul) {
+                    Some(PllPlan { pllsrc, pllxtpre, mul })
+                } else {
+                    None
+                }
+            });
+            Some(found.ok_or(ClockError::SysclkUnreachable)?)
+        };
+
+        let (hpre_div, hclk) = pick_divider(sysclk, self.hclk.unwrap_or(sysclk), &HPRE_DIVIDERS)
+            .ok_or(ClockError::HclkUnreachable)?;
+
+        if self.pclk1.is_some_and(|f| f > PCLK1_MAX) {
+            return Err(ClockError::Pclk1TooHigh);
+        }
+        let (ppre1_div, pclk1) = pick_divider(hclk, self.pclk1.unwrap_or(PCLK1_MAX), &PPRE_DIVIDERS)
+            .ok_or(ClockError::Pclk1Unreachable)?;
+
+        if self.pclk2.is_some_and(|f| f > SYSCLK_MAX) {
+            return Err(ClockError::Pclk2TooHigh);
+        }
+        let (ppre2_div, pclk2) = pick_divider(hclk, self.pclk2.unwrap_or(hclk), &PPRE_DIVIDERS)
+            .ok_or(ClockError::Pclk2Unreachable)?;
+
+        if self.adcclk.is_some_and(|f| f > ADCCLK_MAX) {
+            return Err(ClockError::AdcClockTooHigh);
+        }
+        let (adcpre_div, adcclk) = pick_divider(pclk2, self.adcclk.unwrap_or(ADCCLK_MAX), &ADCPRE_DIVIDERS)
+            .ok_or(ClockError::AdcClockUnreachable)?;
+
+        let latency = match sysclk {
+            0..=24_000_000 => FLASH_LATENCY::_0WS,
+            24_000_001..=48_000_000 => FLASH_LATENCY::_1WS,
+            _ => FLASH_LATENCY::_2WS,
+        };
+
+        Ok(ClockPlan {
+            source: self.source,
+            pll,
+            hpre: hpre_div.1,
+            ppre1: ppre1_div.1,
+            ppre2: ppre2_div.1,
+            adcpre: adcpre_div.1,
+            latency,
+            clocks: Clocks {
+                source: self.source,
+                sysclk,
+                hclk,
+                pclk1,
+                pclk2,
+                ppre1: ppre1_div.0,
+                ppre2: ppre2_div.0,
+                adcclk,
+            },
+        })
+    }
+}
+
+/// `ready` 가 true 가 될 때까지 최대 `CLOCK_READY_TIMEOUT` cycle 대기. 시간이 지나면 `false`
+fn wait_until(ready: impl Fn() -> bool) -> bool {
+    let deadline = Deadline::after_cycles(CLOCK_READY_TIMEOUT);
+    while !ready() {
+        if deadline.expired() {
+            return false;
+        }
+    }
+    true
+}
+
+/// (divider, register bits)
+const HPRE_DIVIDERS: [(u32, u32); 9] = [
+    (1, 0b0000), (2, 0b1000), (4, 0b1001), (8, 0b1010), (16, 0b1011),
+    (64, 0b1100), (128, 0b1101), (256, 0b1110), (512, 0b1111),
+];
+const PPRE_DIVIDERS: [(u32, u32); 5] = [(1, 0b000), (2, 0b100), (4, 0b101), (8, 0b110), (16, 0b111)];
+const ADCPRE_DIVIDERS: [(u32, u32); 4] = [(2, 0b00), (4, 0b01), (6, 0b10), (8, 0b11)];
+
+/// `input / divider <= target` 를 만족하는 가장 작은 분주기
+fn pick_divider(input: u32, target: u32, dividers: &[(u32, u32)]) -> Option<((u32, u32), u32)> {
+    dividers
+        .iter()
+        .find(|&&(div, _)| input / div <= target)
+        .map(|&(div, bits)| ((div, bits), input / div))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct PllPlan {
+    /// false: HSI/2, true: HSE (PREDIV1)
+    pllsrc: bool,
+    /// HSE divided by 2 for PLL entry
+    pllxtpre: bool,
+    mul: u32,
+}
+
+struct ClockPlan {
+    source: ClockSource,
+    pll: Option<PllPlan>,
+    hpre: u32,
+    ppre1: u32,
+    ppre2: u32,
+    adcpre: u32,
+    latency: FLASH_LATENCY,
+    clocks: Clocks,
+}
+
+/// ## Clocks - frozen clock frequencies (Hz)
+/// `rcc::freeze` 또는 `rcc::read_clocks` 가 돌려주는 값. 드라이버는 8 MHz 를 가정하지 말고 이 값을 받아 사용한다.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Clocks {
+    source: ClockSource,
+    sysclk: u32,
+    hclk: u32,
+    pclk1: u32,
+    pclk2: u32,
+    ppre1: u32,
+    ppre2: u32,
+    adcclk: u32,
+}
+
+impl Clocks {
+    /// SYSCLK (또는 PLL) 의 원천. HSE 가 실패해 HSI 로 fallback 했는지 확인할 때 사용
+    pub const fn source(&self) -> ClockSource {
+        self.source
+    }
+    pub const fn sysclk(&self) -> u32 {
+        self.sysclk
+    }
+    pub const fn hclk(&self) -> u32 {
+        self.hclk
+    }
+    pub const fn pclk1(&self) -> u32 {
+        self.pclk1
+    }
+    pub const fn pclk2(&self) -> u32 {
+        self.pclk2
+    }
+    /// TIM2..7 clock: APB1 prescaler 가 1 이 아니면 PCLK1 x2
+    pub const fn pclk1_tim(&self) -> u32 {
+        if self.ppre1 == 1 { self.pclk1 } else { self.pclk1 * 2 }
+    }
+    /// TIM1 clock: APB2 prescaler 가 1 이 아니면 PCLK2 x2
+    pub const fn pclk2_tim(&self) -> u32 {
+        if self.ppre2 == 1 { self.pclk2 } else { self.pclk2 * 2 }
+    }
+    pub const fn adcclk(&self) -> u32 {
+        self.adcclk
+    }
+}
+
+/// ## ResetFlags - RCC_CSR reset flags
+/// 여러 플래그가 동시에 설정될 수 있다 (ex. POR, 소프트웨어 리셋도 NRST 핀을 구동하므로 PINRSTF 가 같이 뜬다). <br/>
+/// 대표 원인은 `cause()` 로 얻는다.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ResetFlags(u32);
+
+impl ResetFlags {
+    /// PINRSTF: NRST pin
+    pub const PIN: ResetFlags = ResetFlags(1 << 26);
+    /// PORRSTF: POR/PDR
+    pub const POR: ResetFlags = ResetFlags(1 << 27);
+    /// SFTRSTF: software reset (SYSRESETREQ)
+    pub const SOFTWARE: ResetFlags = ResetFlags(1 << 28);
+    /// IWDGRSTF: independent watchdog
+    pub const IWDG: ResetFlags = ResetFlags(1 << 29);
+    /// WWDGRSTF: window watchdog
+    pub const WWDG: ResetFlags = ResetFlags(1 << 30);
+    /// LPWRRSTF: Stop / Standby 진입 시 리셋 (option byte nRST_STOP / nRST_STDBY)
+    pub const LOW_POWER: ResetFlags = ResetFlags(1 << 31);
+
+    const MASK: u32 = 0b11_1111 << 26;
+
+    /// RCC_CSR 값에서 reset flag 비트만 추출
+    pub const fn from_csr(csr: u32) -> ResetFlags {
+        ResetFlags(csr & Self::MASK)
+    }
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+    pub const fn contains(&self, flag: ResetFlags) -> bool {
+        self.0 & flag.0 == flag.0 && flag.0 != 0
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// 대표 원인: POR > Low-power > IWDG > WWDG > Software > Pin
+    pub const fn cause(&self) -> ResetCause {
+        if self.contains(Self::POR) {
+            ResetCause::PowerOn
+        } else if self.contains(Self::LOW_POWER) {
+            ResetCause::LowPower
+        } else if self.contains(Self::IWDG) {
+            ResetCause::IndependentWatchdog
+        } else if self.contains(Self::WWDG) {
+            ResetCause::WindowWatchdog
+        } else if self.contains(Self::SOFTWARE) {
+            ResetCause::Software
+        } else if self.contains(Self::PIN) {
+            ResetCause::Pin
+        } else {
+            ResetCause::Unknown
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ResetCause {
+    PowerOn,
+    LowPower,
+    IndependentWatchdog,
+    WindowWatchdog,
+    Software,
+    Pin,
+    /// 플래그가 이미 지워진 경우 (RMVF 후 리셋 없이 다시 읽음 등)
+    Unknown,
+}
+
+impl ResetCause {
+    /// 16x2 LCD 한 줄에 들어가는 이름
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ResetCause::PowerOn => "Power-on",
+            ResetCause::LowPower => "Low-power",
+            ResetCause::IndependentWatchdog => "IWDG",
+            ResetCause::WindowWatchdog => "WWDG",
+            ResetCause::Software => "Software",
+            ResetCause::Pin => "NRST pin",
+            ResetCause::Unknown => "Unknown",
+        }
+    }
+}
+
+/// ## ClockDependent
+/// 클럭 주파수로부터 타이밍 레지스터를 계산하는 드라이버. `rcc::switch_clocks` 또는 <br/>
+/// `notify_clocks_changed` 가 새 `Clocks` 로 호출한다.
+pub trait ClockDependent {
+    fn clocks_changed(&self, clocks: &Clocks);
+}
+
+/// 등록된 드라이버 모두에 새 `Clocks` 를 알린다. CSS 복구처럼 `switch_clocks` 밖에서 클럭이 바뀐 경우에 사용
+pub fn notify_clocks_changed(clocks: &Clocks, dependents: &[&dyn ClockDependent]) {
+    for dependent in dependents {
+        dependent.clocks_changed(clocks);
+    }
+}
+
 pub struct rcc {
     base: u32,
-    cr: *mut u32,
-    cfgr: *mut u32,
-    apb2enr: *mut u32,
-    apb1enr: *mut u32,
-    ahbenr: *mut u32,
-    bdcr: *mut u32,
-    csr: *mut u32,
+    cr: Reg,
+    cfgr: Reg,
+    cir: Reg,
+    apb2rstr: Reg,
+    apb1rstr: Reg,
+    apb2enr: Reg,
+    apb1enr: Reg,
+    ahbenr: Reg,
+    bdcr: Reg,
+    csr: Reg,
 }
 #[allow(non_snake_case)]
 impl rcc {
-    pub fn new() -> rcc{
-        let base_addr = RCC_BASE;
+    pub(super) fn new() -> rcc{
         rcc {
             base:       RCC_BASE,
-            cr:         (RCC_BASE + 0x00) as *mut u32,
-            cfgr:       (RCC_BASE + 0x04) as *mut u32,
-            apb2enr:    (RCC_BASE + 0x18) as *mut u32,
-            apb1enr:    (RCC_BASE + 0x1C) as *mut u32,
-            ahbenr:     (RCC_BASE + 0x14) as *mut u32,
-            bdcr:       (RCC_BASE + 0x20) as *mut u32,
-            csr:        (RCC_BASE + 0x24) as *mut u32,
+            cr:         Reg::at(RCC_BASE, 0x00),
+            cfgr:       Reg::at(RCC_BASE, 0x04),
+            cir:        Reg::at(RCC_BASE, 0x08),
+            apb2rstr:   Reg::at(RCC_BASE, 0x0C),
+            apb1rstr:   Reg::at(RCC_BASE, 0x10),
+            apb2enr:    Reg::at(RCC_BASE, 0x18),
+            apb1enr:    Reg::at(RCC_BASE, 0x1C),
+            ahbenr:     Reg::at(RCC_BASE, 0x14),
+            bdcr:       Reg::at(RCC_BASE, 0x20),
+            csr:        Reg::at(RCC_BASE, 0x24),
         }
     }
 
 
     /// ## CR_HSION -  HSI ON 
-    /// HSI oscillator enabled
-    pub fn cr_hsion(&self) {
-        unsafe {
-            let mut rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val |= (1 << 0); // Enable HSI
-            self.cr.write_volatile(rcc_cr_val);
-            while self.cr.read_volatile() & (1 << 1) == 0 {} // Wait until HSI is ready
-        }
+    /// HSI oscillator enabled. `CLOCK_READY_TIMEOUT` 안에 HSIRDY 가 뜨지 않으면 `false`.
+    pub fn cr_hsion(&self) -> bool {
+        self.cr.set_bit(0, true); // Enable HSI
+        wait_until(|| self.cr.read_bit(1)) // Wait until HSI is ready
     }
 
     pub fn read_cr_pllrdy(&self) -> bool {
-        unsafe {
-            let rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val & (1 << 25) != 0
-        }
+        self.cr.read_bit(25)
     }
 
     pub fn read_cfgr(&self) -> u32 {
-        unsafe {
-            self.cfgr.read_volatile()
-        }
+        self.cfgr.read()
     }
     pub fn read_cr(&self) -> u32 {
-        unsafe {
-            self.cr.read_volatile()
+        self.cr.read()
+    }
+
+    /// ## set_sys_clock_32MHz
+    /// `freeze(ClockConfig::new().sysclk(32_000_000))` 와 같다 (HSI/2 x8, PCLK1 32 MHz).
+    pub fn set_sys_clock_32MHz(&self) -> Result<Clocks, ClockError> {
+        self.freeze(ClockConfig::new().sysclk(32_000_000))
+    }
+
+    /// ## set_sys_clock_64MHz
+    /// `freeze(ClockConfig::new().sysclk(64_000_000).pclk1(32_000_000))` 와 같다 (HSI/2 x16). <br/>
+    /// APB1 은 36 MHz 가 최대이므로 PPRE1 은 /2.
+    pub fn set_sys_clock_64MHz(&self) -> Result<Clocks, ClockError> {
+        self.freeze(ClockConfig::new().sysclk(64_000_000).pclk1(32_000_000))
+    }
+
+    /// ## switch_clocks - 실행 중 클럭 변경
+    /// `freeze` 로 새 클럭을 적용한 뒤 `dependents` 에 등록된 드라이버들에 새 `Clocks` 를 알려 <br/>
+    /// 타이밍 (I2C FREQ/CCR/TRISE 등) 을 다시 계산하게 한다. ADCPRE 와 `utils::delay` 는 `freeze` 가 갱신한다. <br/>
+    /// 변경 중에는 등록된 드라이버로 전송하지 않아야 한다.
+    /// ```ignore
+    /// // 64 MHz -> HSI 8 MHz (저전력) -> 다시 64 MHz
+    /// rcc.switch_clocks(ClockConfig::new(), &[&lcd])?;
+    /// rcc.switch_clocks(ClockConfig::new().sysclk(64_000_000), &[&lcd])?;
+    /// ```
+    pub fn switch_clocks(&self, config: ClockConfig, dependents: &[&dyn ClockDependent]) -> Result<Clocks, ClockError> {
+        let clocks = self.freeze(config)?;
+        notify_clocks_changed(&clocks, dependents);
+        Ok(clocks)
+    }
+
+    /// ## CR_HSEON - HSE ON
+    /// HSEBYP 는 HSE 가 꺼진 상태에서만 쓸 수 있으므로 먼저 HSEON 을 끄고 설정한다. <br/>
+    /// `HSE_STARTUP_TIMEOUT` 안에 HSERDY 가 뜨지 않으면 HSE 를 다시 끄고 `false`.
+    pub fn cr_hseon(&self, mode: HseMode) -> bool {
+        if self.cr.read_bit(17) && self.cr.read_bit(18) == (mode == HseMode::Bypass) {
+            return true; // 이미 같은 모드로 동작 중
+        }
+        if !self.cr_hseoff() {
+            return false;
+        }
+        self.cr.set_bit(18, mode == HseMode::Bypass); // HSEBYP
+        self.cr.set_bit(16, true); // HSEON
+        for _ in 0..HSE_STARTUP_TIMEOUT {
+            if self.cr.read_bit(17) {
+                return true; // HSERDY
+            }
+        }
+        self.cr_hseoff();
+        false
+    }
+
+    /// ## CR_HSEOFF - HSE OFF
+    /// SYSCLK / PLL 이 HSE 를 쓰는 중에는 하드웨어가 무시한다. <br/>
+    /// `HSE_STARTUP_TIMEOUT` 안에 HSERDY 가 내려가지 않으면 HSEBYP 는 건드리지 않고 `false`.
+    pub fn cr_hseoff(&self) -> bool {
+        self.cr.set_bit(16, false); // HSEOFF
+        for _ in 0..HSE_STARTUP_TIMEOUT {
+            if !self.cr.read_bit(17) {
+                self.cr.set_bit(18, false); // HSEBYP (HSE 가 꺼진 상태에서만 쓸 수 있음)
+                return true;
+            }
         }
+        false
+    }
+
+    pub fn read_cr_hserdy(&self) -> bool {
+        self.cr.read_bit(17)
+    }
+
+    /// ## CR_CSSON - Clock Security System
+    /// HSE 가 멈추면 하드웨어가 HSE / PLL 을 끄고 SYSCLK 를 HSI 로 바꾼 뒤 NMI 를 발생시킨다. <br/>
+    /// NMI 의 `css_recover` 와 CR read-modify-write 가 겹치지 않도록 bit-band 로 한 비트만 쓴다.
+    pub fn cr_csson(&self, enable: bool) {
+        self.cr.bit_band(19).write(enable);
+    }
+
+    /// CIR_CSSF - HSE failure 감지 플래그
+    pub fn read_cir_cssf(&self) -> bool {
+        self.cir.read_bit(7)
+    }
+
+    /// CIR_CSSC - CSSF clear (write 1)
+    pub fn cir_cssc(&self) {
+        self.cir.set_bit(23, true);
+    }
+
+    /// ## css_recover - NMI handler 에서 호출
+    /// CSSF 가 아니면 `None`. CSSF 를 클리어하고 `config` 의 HSI fallback 설정 <br/>
+    /// (HSI/2 PLL) 으로 SYSCLK 를 다시 올린 뒤 새 `Clocks` 를 돌려준다. <br/>
+    /// fallback 설정도 적용할 수 없으면 (PLL 이 lock 되지 않는 등) 지금 동작 중인 클럭 (보통 HSI 8 MHz) 을 돌려준다. <br/>
+    /// `freeze` 의 대기는 모두 cycle 한도가 있으므로 NMI 안에서 멈추지 않는다.
+    pub fn css_recover(&self, config: ClockConfig) -> Option<Clocks> {
+        if !self.read_cir_cssf() {
+            return None;
+        }
+        self.cir_cssc();
+        let clocks = self.freeze(config.hsi_fallback()).unwrap_or_else(|_| self.read_clocks());
+        Some(clocks)
+    }
+
+    /// ## CSR reset flags
+    /// 다음 리셋까지 유지되므로 부팅 직후 읽고 `csr_rmvf` 로 지운다.
+    pub fn read_reset_flags(&self) -> ResetFlags {
+        ResetFlags::from_csr(self.csr.read())
     }
 
-    pub fn set_sys_clock_32MHz(&self) {
-        unsafe {
-            let mut rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val |= (1 << 0); // HSION
-            self.cr.write_volatile(rcc_cr_val);
-            while (self.cr.read_volatile() & (1 << 1)) == 0 {} // Wait until HSIRDY
+    /// CSR_RMVF - Remove reset flags
+    pub fn csr_rmvf(&self) {
+        self.csr.set_bit(24, true);
+    }
+
+    /// reset flag 를 읽고 바로 클리어
+    pub fn take_reset_flags(&self) -> ResetFlags {
+        let flags = self.read_reset_flags();
+        self.csr_rmvf();
+        flags
+    }
+
+    /// ## freeze - clock tree 적용
+    /// `ClockConfig` 를 검증하고 FLASH latency, prescaler, PLL, SW 를 순서대로 설정한 뒤 <br/>
+    /// 실제 주파수를 `Clocks` 로 돌려준다. 요청이나 그 HSI fallback 을 만들 수 없으면 레지스터를 건드리지 않고 `Err`. <br/>
+    /// HSE 가 timeout 안에 준비되지 않으면 `ClockConfig::hsi_fallback` 설정으로 대신 동작하며, <br/>
+    /// 이 경우 `Clocks::source()` 가 `ClockSource::Hsi` 이다. <br/>
+    /// CSS 는 처음에 끄고 모든 CR / CFGR 쓰기 (MCO 포함) 가 끝난 뒤 다시 켜므로, 그 사이에는 CSS NMI 가 끼어들지 않는다. <br/>
+    /// HSI / PLL 이 준비되지 않거나 SWS 가 바뀌지 않으면 `ClockError::ReadyTimeout`. 이때 CSS 는 꺼져 있고, <br/>
+    /// `utils::delay` 는 `read_clocks` 의 현재 SYSCLK 에 맞춘다.
+    pub fn freeze(&self, config: ClockConfig) -> Result<Clocks, ClockError> {
+        let plan = config.plan()?;
+        // HSE 가 뜨지 않을 때 쓸 설정도 레지스터를 건드리기 전에 검증한다 (HSI 설정이면 같은 값)
+        let fallback = config.hsi_fallback().plan()?;
+
+        self.cr_csson(false);
+        let result = self.apply(plan, fallback, config.mco);
+        if result.is_err() {
+            set_sys_clk(self.read_clocks().sysclk());
+        }
+        result
+    }
+
+    /// `freeze` 의 레지스터 쓰기. 모든 ready / SWS 대기는 `CLOCK_READY_TIMEOUT` 으로 제한한다.
+    fn apply(&self, mut plan: ClockPlan, fallback: ClockPlan, mco: Option<Mco>) -> Result<Clocks, ClockError> {
+        // PLL / prescaler 변경 중에는 HSI 로 동작
+        if !self.cr_hsion() {
+            return Err(ClockError::ReadyTimeout);
+        }
+        self.cfgr.write_field(0, 0b11, 0b00); // SW = HSI
+        self.wait_sws(0b00)?;
+        self.cr.set_bit(24, false); // PLLOFF (PLL 설정은 꺼진 상태에서만 가능)
+        if !wait_until(|| !self.cr.read_bit(25)) {
+            return Err(ClockError::ReadyTimeout); // PLL unlocked 대기
+        }
+
+        match plan.source {
+            ClockSource::Hse(_, mode) => {
+                if !self.cr_hseon(mode) {
+                    plan = fallback;
+                }
+            }
+            ClockSource::Hsi => {
+                // SW = HSI, PLL off 이후이므로 정상이면 꺼진다. 실패해도 HSI 로 동작하는 데는 문제 없음
+                self.cr_hseoff();
+            }
+        }
 
-            let flash = FLASH::new(0x4002_2000);
-            flash.ACR_LATENCY(FLASH_LATENCY::_1WS); // Set flash latency
-            flash.ACR_PRFTBE(true); // Enable prefetch buffer
+        // 클럭을 올리기 전에 wait state 를 먼저 늘린다
+        let flash = FLASH::new(FLASH_BASE);
+        flash.ACR_LATENCY(plan.latency);
+        flash.ACR_PRFTBE(true); // Enable prefetch buffer
 
-            let mut rcc_cfgr_val = self.cfgr.read_volatile();
+        self.cfgr.write_field(4, 0b1111, plan.hpre); // HPRE
+        self.cfgr.write_field(8, 0b111, plan.ppre1); // PPRE1
+        self.cfgr.write_field(11, 0b111, plan.ppre2); // PPRE2
+        self.cfgr.write_field(14, 0b11, plan.adcpre); // ADCPRE
 
-            // rcc_cfgr_val &= !(0b1 << 24); // Clear PLLON bit 
-            rcc_cfgr_val &= !(1 << 16); // Clear PLLSRC bit (select HSI/2)
-            rcc_cfgr_val &= !(0b1111 << 18); // Clear PLLMUL bits
-            rcc_cfgr_val |= (0b0110 << 18); // Set PLLMUL to 8 (4 MHz * 8 = 32 MHz)
-            // rcc_cfgr_val |= (0b1110 << 18); // Set PLLMUL to 8 (4 MHz * 16 = 64 MHz)
-            self.cfgr.write_volatile(rcc_cfgr_val);
+        let sw = match (plan.pll, plan.source) {
+            (Some(pll), _) => {
+                self.cfgr.set_bit(16, pll.pllsrc); // PLLSRC
+                self.cfgr.set_bit(17, pll.pllxtpre); // PLLXTPRE
+                self.cfgr.write_field(18, 0b1111, pll.mul - 2); // PLLMUL
+                if !self.cr_pllon() {
+                    return Err(ClockError::ReadyTimeout);
+                }
+                0b10
+            }
+            (None, ClockSource::Hse(..)) => 0b01,
+            (None, ClockSource::Hsi) => 0b00,
+        };
 
-            rcc_cfgr_val = self.cfgr.read_volatile();
-            rcc_cfgr_val &= !(0b11 << 0); // Clear SW bits
-            rcc_cfgr_val |= (0b10 << 0); // Set SW to 0b10 (PLL selected as system clock)
-            self.cfgr.write_volatile(rcc_cfgr_val);
+        self.cfgr.write_field(0, 0b11, sw); // SW
+        self.wait_sws(sw)?;
+        if let Some(mco) = mco {
+            self.cfgr.write_field(24, 0b111, mco as u32); // MCO
+        }
+        set_sys_clk(plan.clocks.sysclk()); // utils::delay 루프 횟수 갱신
 
-            rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val |= (1 << 24); // PLLON
-            self.cr.write_volatile(rcc_cr_val);
-            while (self.cr.read_volatile() & (1 << 25)) == 0 {} // Wait until PLLRDY
+        // HSE 를 쓰는 동안만 Clock Security System 으로 감시
+        self.cr_csson(matches!(plan.source, ClockSource::Hse(..)));
 
-            while (self.cfgr.read_volatile() & (0b11 << 2)) != (0b10 << 2) {} // Wait until SWS is PLL
+        Ok(plan.clocks)
+    }
 
-            delay_sys_clk_ms(100);
+    /// SWS 가 `sw` 가 될 때까지 대기
+    fn wait_sws(&self, sw: u32) -> Result<(), ClockError> {
+        if wait_until(|| self.cfgr.read_field(2, 0b11) == sw) {
+            Ok(())
+        } else {
+            Err(ClockError::ReadyTimeout)
         }
     }
-    
-    pub fn set_sys_clock_64MHz(&self) {
-        unsafe {
-            let mut rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val |= (1 << 0); // HSION
-            self.cr.write_volatile(rcc_cr_val);
-            while (self.cr.read_volatile() & (1 << 1)) == 0 {} // Wait until HSIRDY
-    
-            let flash = FLASH::new(0x4002_2000);
-            flash.ACR_LATENCY(FLASH_LATENCY::_2WS); // Set flash latency for 64 MHz
-            flash.ACR_PRFTBE(true); // Enable prefetch buffer
-    
-            let mut rcc_cfgr_val = self.cfgr.read_volatile();
-    
-            // HSI/2를 PLL 소스로 선택하고 PLL 곱셈 인자를 16으로 설정
-            rcc_cfgr_val &= !(1 << 16); // Clear PLLSRC bit (select HSI/2)
-            rcc_cfgr_val &= !(0b1111 << 18); // Clear PLLMUL bits
-            rcc_cfgr_val |= (0b1110 << 18); // Set PLLMUL to 16 (8 MHz / 2 * 16 = 64 MHz)
-            self.cfgr.write_volatile(rcc_cfgr_val);
-    
-            rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val |= (1 << 24); // PLLON
-            self.cr.write_volatile(rcc_cr_val);
-            while (self.cr.read_volatile() & (1 << 25)) == 0 {} // Wait until PLLRDY
-    
-            rcc_cfgr_val = self.cfgr.read_volatile();
-            rcc_cfgr_val &= !(0b11 << 0); // Clear SW bits
-            rcc_cfgr_val |= (0b10 << 0); // Set SW to 0b10 (PLL selected as system clock)
-            self.cfgr.write_volatile(rcc_cfgr_val);
-    
-            while (self.cfgr.read_volatile() & (0b11 << 2)) != (0b10 << 2) {} // Wait until SWS is PLL
-    
-            delay_sys_clk_ms(100);
+
+    /// ## read_clocks - 현재 CFGR 설정으로부터 주파수 계산
+    /// HSE 는 `HSE_VALUE` 로 가정한다.
+    pub fn read_clocks(&self) -> Clocks {
+        let cfgr = self.cfgr.read();
+        let hse_mode = if self.cr.read_bit(18) { HseMode::Bypass } else { HseMode::Crystal };
+        let hse = ClockSource::Hse(HSE_VALUE, hse_mode);
+
+        let source = match (cfgr >> 2) & 0b11 {
+            0b01 => hse,
+            0b10 if (cfgr >> 16) & 0b1 == 1 => hse,
+            _ => ClockSource::Hsi,
+        };
+        let sysclk = match (cfgr >> 2) & 0b11 {
+            0b00 => HSI_VALUE,
+            0b01 => HSE_VALUE,
+            0b10 => {
+                let pll_clk_in = if (cfgr >> 16) & 0b1 == 0 {
+                    HSI_VALUE / 2
+                } else if (cfgr >> 17) & 0b1 == 1 {
+                    HSE_VALUE / 2
+                } else {
+                    HSE_VALUE
+                };
+                // PLLMUL 0b1111 도 x16
+                let pllmul = (((cfgr >> 18) & 0b1111) + 2).min(16);
+                pll_clk_in * pllmul
+            }
+            _ => 0, // 예약된 값 (사용되지 않음)
+        };
+
+        let divider_of = |bits: u32, dividers: &[(u32, u32)]| {
+            dividers.iter().find(|&&(_, b)| b == bits).map_or(1, |&(div, _)| div)
+        };
+        // HPRE 0xxx, PPREx 0xx 는 모두 분주 없음
+        let hpre = (cfgr >> 4) & 0b1111;
+        let hpre = if hpre & 0b1000 == 0 { 0 } else { hpre };
+        let ppre1 = (cfgr >> 8) & 0b111;
+        let ppre1 = if ppre1 & 0b100 == 0 { 0 } else { ppre1 };
+        let ppre2 = (cfgr >> 11) & 0b111;
+        let ppre2 = if ppre2 & 0b100 == 0 { 0 } else { ppre2 };
+        let ppre1 = divider_of(ppre1, &PPRE_DIVIDERS);
+        let ppre2 = divider_of(ppre2, &PPRE_DIVIDERS);
+        let adcpre = divider_of((cfgr >> 14) & 0b11, &ADCPRE_DIVIDERS);
+
+        let hclk = sysclk / divider_of(hpre, &HPRE_DIVIDERS);
+        let pclk2 = hclk / ppre2;
+        Clocks {
+            source,
+            sysclk,
+            hclk,
+            pclk1: hclk / ppre1,
+            pclk2,
+            ppre1,
+            ppre2,
+            adcclk: pclk2 / adcpre,
         }
     }
 
@@ -151,114 +852,423 @@ impl rcc {
     /// - 10: PCLK2 divided by 6 <br/>
     /// - 11: PCLK2 divided by 8 <br/>
     pub fn cfgr_adcpre(&self , adcpre: u32) {
-        unsafe {
-            let mut rcc_cfgr_val = self.cfgr.read_volatile();
-            rcc_cfgr_val &= !(0b11 << 14); // Clear ADCPRE bits
-            rcc_cfgr_val |= (adcpre << 14); // Set ADCPRE bits
-            self.cfgr.write_volatile(rcc_cfgr_val);
-        }
-        
+        self.cfgr.write_field(14, 0b11, adcpre); // Set ADCPRE bits
     }
 
-    fn cr_pllon(&self) {
-        unsafe {
-            let mut rcc_cr_val = self.cr.read_volatile();
-            rcc_cr_val |= (1 << 24); // Enable PLL
-            self.cr.write_volatile(rcc_cr_val);
-            while self.cr.read_volatile() & (1 << 25) == 0 {} // Wait until PLL is ready
-        }
+    fn cr_pllon(&self) -> bool {
+        self.cr.set_bit(24, true); // Enable PLL
+        wait_until(|| self.cr.read_bit(25)) // Wait until PLL is ready
     }
 
-    pub fn APB2ENR(&self) -> *mut u32 {
-        (self.base + 0x18) as *mut u32
+    /// ## CFGR_MCO - clock output on PA8
+    /// MCO 소스를 선택한다. PA8 은 호출 전에 alternate function push-pull 로 설정해 두어야 한다 <br/>
+    /// (`gpioa.p8.into_alternate_push_pull(Speed::Mhz50)`). `Mco::NoClock` 이면 출력만 끈다. <br/>
+    /// **IMPORTANT** CFGR read-modify-write 이므로 CSS 가 켜진 뒤 (HSE 로 `freeze` 한 뒤) 에는 <br/>
+    /// NMI 의 `css_recover` 와 겹칠 수 있다. 그 경우 `ClockConfig::mco` 로 `freeze` 안에서 설정한다.
+    pub fn cfgr_mco(&self, mco: Mco, _pa8: &PA8<Alternate<PushPull>>) {
+        self.cfgr.write_field(24, 0b111, mco as u32);
     }
-    pub fn enable_adc1(&self) {
-        unsafe {
-            let mut apb2enr_val = self.apb2enr.read_volatile();
-            apb2enr_val |= (1 << 9);  // ADC1 클럭 활성화
-            self.apb2enr.write_volatile(apb2enr_val);
+
+    fn enr(&self, bus: Bus) -> Reg {
+        match bus {
+            Bus::AHB => self.ahbenr,
+            Bus::APB1 => self.apb1enr,
+            Bus::APB2 => self.apb2enr,
         }
     }
-    pub fn APB2ENR_ADC1EN(&self, enable: bool) {
-        unsafe {
-            let mut apb2enr_val = self.apb2enr.read_volatile();
-            if enable {
-                apb2enr_val |= (1 << 9); // Enable ADC1
-            } else {
-                apb2enr_val &= !(1 << 9); // Disable ADC1
-            }
-            self.apb2enr.write_volatile(apb2enr_val);
-        }
+
+    /// ## Peripheral clock enable / disable (AHBENR, APB1ENR, APB2ENR)
+    /// xxxENR 은 여러 드라이버가 공유하므로 bit-band 로 한 비트만 쓴다 (ISR 과 경쟁 없음)
+    pub fn set_clock_enabled(&self, peripheral: Peripheral, enable: bool) {
+        self.enr(peripheral.bus()).bit_band(peripheral.bit()).write(enable);
     }
-    pub fn ABP2ENR_AFIOEN(&self, enable: bool) {
-        unsafe {
-            let mut apb2enr_val = self.apb2enr.read_volatile();
-            if enable {
-                apb2enr_val |= (1 << 0); // Enable AFIO
-            } else {
-                apb2enr_val &= !(1 << 0); // Disable AFIO
-            }
-            self.apb2enr.write_volatile(apb2enr_val);
-        }
+    pub fn enable_clock(&self, peripheral: Peripheral) {
+        self.set_clock_enabled(peripheral, true);
     }
-    pub fn APB2ENR_IOPx_EN(&self, iop_x_en: IOPxEN, enable: bool) {
-        unsafe {
-            let mut apb2enr_val = self.apb2enr.read_volatile();
-            let bit = iop_x_en as u32;
-            if enable {
-                apb2enr_val |= (1 << bit); // Enable IOPx
-            } else {
-                apb2enr_val &= !(1 << bit); // Disable IOPx
-            }
-            self.apb2enr.write_volatile(apb2enr_val);
-        }
+    pub fn disable_clock(&self, peripheral: Peripheral) {
+        self.set_clock_enabled(peripheral, false);
     }
-    fn APB1ENR(&self) -> *mut u32 {
-        (self.base + 0x1C) as *mut u32
+    pub fn is_clock_enabled(&self, peripheral: Peripheral) -> bool {
+        self.enr(peripheral.bus()).read_bit(peripheral.bit())
     }
-    pub fn APB1ENR_I2C1EN(&self, enable: bool) {
-        unsafe {
-            let mut apb1enr_val = self.apb1enr.read_volatile();
-            if enable {
-                apb1enr_val |= (1 << 21); // Enable I2C1
-            } else {
-                apb1enr_val &= !(1 << 21); // Disable I2C1
-            }
-            self.apb1enr.write_volatile(apb1enr_val);
-        }
+
+    /// ## reset_peripheral - APB1RSTR / APB2RSTR pulse
+    /// reset 비트를 set 후 clear 하여 peripheral 레지스터를 reset 값으로 되돌린다 (clock enable 은 유지). <br/>
+    /// 멈춘 I2C / SPI 복구용. AHB peripheral 은 F1 에 reset 레지스터가 없으므로 아무것도 하지 않고 `false`.
+    pub fn reset_peripheral(&self, peripheral: Peripheral) -> bool {
+        let rstr = match peripheral.bus() {
+            Bus::AHB => return false,
+            Bus::APB1 => self.apb1rstr,
+            Bus::APB2 => self.apb2rstr,
+        };
+        let bit = rstr.bit_band(peripheral.bit());
+        bit.set();
+        bit.clear();
+        true
     }
-    pub fn ABP1ENR_I2C2EN(&self, enable: bool) {
-        unsafe {
-            let mut apb1enr_val = self.apb1enr.read_volatile();
-            if enable {
-                apb1enr_val |= (1 << 22); // Enable I2C2
-            } else {
-                apb1enr_val &= !(1 << 22); // Disable I2C2
-            }
-            self.apb1enr.write_volatile(apb1enr_val);
-        }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+    use crate::peripherals::gpio::{GpioXBase, Parts, Speed};
+
+    const RCC_CR: u32 = RCC_BASE;
+    const RCC_CFGR: u32 = RCC_BASE + 0x04;
+    const RCC_APB1ENR: u32 = RCC_BASE + 0x1C;
+    const RCC_APB2ENR: u32 = RCC_BASE + 0x18;
+
+    #[test]
+    fn cfgr_adcpre_sets_bits_15_14() {
+        mock::reset();
+        mock::poke(RCC_CFGR, 0xFFFF_FFFF);
+        rcc::new().cfgr_adcpre(0b01);
+        assert_eq!(mock::peek(RCC_CFGR), 0xFFFF_7FFF);
+        rcc::new().cfgr_adcpre(0b11);
+        assert_eq!(mock::peek(RCC_CFGR), 0xFFFF_FFFF);
     }
-    pub fn APB1ENR_TIMxEN(&self, tim_x_en: TIMxEN, enable: bool) {
-        unsafe {
-            let mut apb1enr_val = self.apb1enr.read_volatile();
-            let bit = tim_x_en as u32;
-            if enable {
-                apb1enr_val |= (1 << bit); // Enable TIMx
-            } else {
-                apb1enr_val &= !(1 << bit); // Disable TIMx
-            }
-            self.apb1enr.write_volatile(apb1enr_val);
-        }
+
+    #[test]
+    fn clock_enable_bits() {
+        mock::reset();
+        let rcc = rcc::new();
+        rcc.enable_clock(Peripheral::GPIOC);
+        rcc.enable_clock(Peripheral::AFIO);
+        rcc.enable_clock(Peripheral::ADC1);
+        rcc.enable_clock(Peripheral::USART1);
+        assert_eq!(mock::peek(RCC_APB2ENR), (1 << 14) | (1 << 9) | (1 << 4) | (1 << 0));
+        rcc.enable_clock(Peripheral::I2C2);
+        rcc.enable_clock(Peripheral::TIM3);
+        rcc.disable_clock(Peripheral::TIM3);
+        assert_eq!(mock::peek(RCC_APB1ENR), 1 << 22);
+        assert!(rcc.is_clock_enabled(Peripheral::I2C2));
+        assert!(!rcc.is_clock_enabled(Peripheral::TIM3));
+        rcc.enable_clock(Peripheral::DMA1);
+        rcc.enable_clock(Peripheral::CRC);
+        assert_eq!(mock::peek(RCC_BASE + 0x14), (1 << 6) | (1 << 0));
+    }
+
+    #[test]
+    fn peripheral_bus_and_bit() {
+        assert_eq!((Peripheral::USB.bus(), Peripheral::USB.bit()), (Bus::APB1, 23));
+        assert_eq!((Peripheral::CAN.bus(), Peripheral::CAN.bit()), (Bus::APB1, 25));
+        assert_eq!((Peripheral::SPI1.bus(), Peripheral::SPI1.bit()), (Bus::APB2, 12));
+        assert_eq!((Peripheral::TIM11.bus(), Peripheral::TIM11.bit()), (Bus::APB2, 21));
+        assert_eq!((Peripheral::SDIO.bus(), Peripheral::SDIO.bit()), (Bus::AHB, 10));
+    }
+
+    #[test]
+    fn reset_peripheral_pulses_rstr() {
+        mock::reset();
+        let rcc = rcc::new();
+        mock::poke(RCC_APB1ENR, 1 << 22);
+        assert!(rcc.reset_peripheral(Peripheral::I2C2));
+        assert_eq!(mock::writes(Reg::new(RCC_BASE + 0x10).bit_band(22).addr()), [1, 0]);
+        assert_eq!(mock::peek(RCC_BASE + 0x10), 0);
+        assert_eq!(mock::peek(RCC_APB1ENR), 1 << 22); // clock 은 유지
+        assert!(!rcc.reset_peripheral(Peripheral::DMA1));
+    }
+
+    #[test]
+    fn set_sys_clock_64MHz_selects_hsi_div2_times_16() {
+        mock::reset();
+        model_rcc_hardware();
+        mock::poke(RCC_CFGR, 1 << 16); // PLLSRC=HSE (to be cleared)
+        let clocks = rcc::new().set_sys_clock_64MHz().unwrap();
+        let cfgr = mock::peek(RCC_CFGR);
+        assert_eq!((cfgr >> 16) & 1, 0);
+        assert_eq!((cfgr >> 18) & 0b1111, 0b1110);
+        assert_eq!((cfgr >> 8) & 0b111, 0b100); // PPRE1 /2
+        assert_eq!(cfgr & 0b11, 0b10);
+        assert_eq!(mock::peek(RCC_CR) & (1 << 24), 1 << 24);
+        assert_eq!(mock::peek(0x4002_2000) & 0b111, 0b010); // FLASH latency 2WS
+        assert_eq!(clocks.pclk1(), 32_000_000);
+
+        let clocks = rcc::new().set_sys_clock_32MHz().unwrap();
+        assert_eq!((clocks.sysclk(), clocks.pclk1()), (32_000_000, 32_000_000));
+        assert_eq!((mock::peek(RCC_CFGR) >> 18) & 0b1111, 0b0110);
     }
-    pub fn APB1ENR_TIM2EN(&self, enable: bool) {
-        unsafe {
-            let mut apb1enr_val = self.apb1enr.read_volatile();
-            if enable {
-                apb1enr_val |= (1 << 0); // Enable TIM2
-            } else {
-                apb1enr_val &= !(1 << 0); // Disable TIM2
+
+    #[test]
+    fn plan_hsi_64MHz() {
+        let plan = ClockConfig::new().sysclk(64_000_000).adcclk(8_000_000).plan().unwrap();
+        assert_eq!(plan.pll, Some(PllPlan { pllsrc: false, pllxtpre: false, mul: 16 }));
+        assert_eq!(plan.hpre, 0b0000);
+        assert_eq!(plan.ppre1, 0b100); // /2: PCLK1 = 32 MHz <= 36 MHz
+        assert_eq!(plan.ppre2, 0b000);
+        assert_eq!(plan.adcpre, 0b11); // /8
+        assert_eq!(plan.latency, FLASH_LATENCY::_2WS);
+        let clocks = plan.clocks;
+        assert_eq!(clocks.sysclk(), 64_000_000);
+        assert_eq!(clocks.hclk(), 64_000_000);
+        assert_eq!(clocks.pclk1(), 32_000_000);
+        assert_eq!(clocks.pclk1_tim(), 64_000_000);
+        assert_eq!(clocks.pclk2(), 64_000_000);
+        assert_eq!(clocks.adcclk(), 8_000_000);
+    }
+
+    #[test]
+    fn plan_hse_72MHz() {
+        let plan = ClockConfig::new().use_hse(8_000_000).sysclk(72_000_000).plan().unwrap();
+        assert_eq!(plan.pll, Some(PllPlan { pllsrc: true, pllxtpre: false, mul: 9 }));
+        assert_eq!(plan.clocks.pclk1(), 36_000_000);
+        assert_eq!(plan.clocks.adcclk(), 12_000_000); // 72 / 6
+    }
+
+    #[test]
+    fn plan_hse_uses_pllxtpre_when_needed() {
+        let plan = ClockConfig::new().use_hse(8_000_000).sysclk(36_000_000).plan().unwrap();
+        assert_eq!(plan.pll, Some(PllPlan { pllsrc: true, pllxtpre: true, mul: 9 }));
+    }
+
+    #[test]
+    fn plan_without_pll() {
+        let plan = ClockConfig::new().plan().unwrap();
+        assert_eq!(plan.pll, None);
+        assert_eq!(plan.latency, FLASH_LATENCY::_0WS);
+        assert_eq!(plan.clocks.sysclk(), HSI_VALUE);
+        assert_eq!(plan.clocks.adcclk(), 4_000_000);
+    }
+
+    #[test]
+    fn plan_rejects_impossible_requests() {
+        assert_eq!(ClockConfig::new().sysclk(80_000_000).plan().err(), Some(ClockError::SysclkTooHigh));
+        // HSI/2 로는 72 MHz 불가
+        assert_eq!(ClockConfig::new().sysclk(72_000_000).plan().err(), Some(ClockError::SysclkUnreachable));
+        assert_eq!(ClockConfig::new().sysclk(30_000_000).plan().err(), Some(ClockError::SysclkUnreachable));
+        assert_eq!(ClockConfig::new().sysclk(64_000_000).pclk1(64_000_000).plan().err(), Some(ClockError::Pclk1TooHigh));
+        assert_eq!(ClockConfig::new().adcclk(16_000_000).plan().err(), Some(ClockError::AdcClockTooHigh));
+        assert_eq!(ClockConfig::new().use_hse(25_000_000).plan().err(), Some(ClockError::HseOutOfRange));
+    }
+
+    #[test]
+    fn freeze_writes_prescalers() {
+        mock::reset();
+        mock::poke(RCC_CR, 1 << 1); // HSIRDY (SWS 는 HSI 그대로)
+        let rcc = rcc::new();
+        let config = ClockConfig::new().use_hsi().sysclk(8_000_000).hclk(4_000_000);
+        let clocks = rcc.freeze(config).unwrap();
+        assert_eq!(clocks.hclk(), 4_000_000);
+        assert_eq!(clocks.pclk1(), 4_000_000);
+        let cfgr = mock::peek(RCC_CFGR);
+        assert_eq!((cfgr >> 4) & 0b1111, 0b1000); // HPRE /2
+        assert_eq!((cfgr >> 14) & 0b11, 0b00); // ADCPRE /2
+        assert_eq!(cfgr & 0b11, 0b00); // SW = HSI
+        assert_eq!(rcc.read_clocks(), clocks);
+    }
+
+    #[test]
+    fn read_clocks_decodes_apb_prescalers() {
+        mock::reset();
+        // SWS=PLL, HSI/2 x16, PPRE1 /2, ADCPRE /8
+        mock::poke(RCC_CFGR, (0b10 << 2) | (0b100 << 8) | (0b11 << 14) | (0b1110 << 18));
+        let clocks = rcc::new().read_clocks();
+        assert_eq!(clocks.sysclk(), 64_000_000);
+        assert_eq!(clocks.pclk1(), 32_000_000);
+        assert_eq!(clocks.pclk2(), 64_000_000);
+        assert_eq!(clocks.adcclk(), 8_000_000);
+        assert_eq!(clocks.source(), ClockSource::Hsi);
+    }
+
+    #[test]
+    fn hsi_fallback_rounds_sysclk_down() {
+        let config = ClockConfig::new().use_hse_bypass(8_000_000).sysclk(72_000_000).pclk1(36_000_000);
+        let plan = config.hsi_fallback().plan().unwrap();
+        assert_eq!(plan.clocks.source(), ClockSource::Hsi);
+        assert_eq!(plan.clocks.sysclk(), 64_000_000);
+        assert_eq!(plan.clocks.pclk1(), 32_000_000);
+        assert_eq!(ClockConfig::new().sysclk(30_000_000).hsi_fallback().plan().unwrap().clocks.sysclk(), 28_000_000);
+    }
+
+    #[test]
+    fn cr_hseon_keeps_running_bypass_hse() {
+        mock::reset();
+        mock::poke(RCC_CR, (1 << 16) | (1 << 17) | (1 << 18)); // HSEON, HSERDY, HSEBYP
+        let rcc = rcc::new();
+        assert!(rcc.cr_hseon(HseMode::Bypass));
+        assert_eq!(mock::peek(RCC_CR), (1 << 16) | (1 << 17) | (1 << 18));
+    }
+
+    #[test]
+    fn cr_hseon_times_out_and_turns_hse_off() {
+        mock::reset();
+        let rcc = rcc::new();
+        assert!(!rcc.cr_hseon(HseMode::Bypass));
+        assert_eq!(mock::peek(RCC_CR) & ((1 << 16) | (1 << 18)), 0);
+    }
+
+    #[test]
+    fn cr_hseoff_gives_up_when_hserdy_stays_set() {
+        mock::reset();
+        mock::poke(RCC_CR, (1 << 17) | (1 << 18)); // HSERDY 가 내려가지 않음 (HSE 를 SYSCLK 로 사용 중)
+        let rcc = rcc::new();
+        assert!(!rcc.cr_hseoff());
+        assert_eq!(mock::peek(RCC_CR) & (1 << 18), 1 << 18); // HSEBYP 유지
+        assert!(!rcc.cr_hseon(HseMode::Crystal));
+    }
+
+    #[test]
+    fn freeze_falls_back_to_hsi_when_hse_does_not_start() {
+        mock::reset();
+        mock::poke(RCC_CR, 1 << 1); // HSIRDY, HSE never ready
+        let rcc = rcc::new();
+        let clocks = rcc.freeze(ClockConfig::new().use_hse_bypass(8_000_000)).unwrap();
+        assert_eq!(clocks.source(), ClockSource::Hsi);
+        assert_eq!(clocks.sysclk(), HSI_VALUE);
+        assert_eq!(mock::peek(RCC_CFGR) & 0b11, 0b00); // SW = HSI
+        assert_eq!(mock::peek(RCC_CR) & (1 << 19), 0); // CSS off
+    }
+
+    /// RCC_CR / RCC_CFGR 의 ready / status 비트를 쓰기에 맞춰 갱신하는 mock 하드웨어
+    fn model_rcc_hardware() {
+        mock::on_write(RCC_CR, |cr| {
+            let ready = |on: u32, rdy: u32| if cr & (1 << on) != 0 { 1 << rdy } else { 0 };
+            (cr & !((1 << 1) | (1 << 17) | (1 << 25))) | ready(0, 1) | ready(16, 17) | ready(24, 25)
+        });
+        mock::on_write(RCC_CFGR, |cfgr| (cfgr & !(0b11 << 2)) | ((cfgr & 0b11) << 2)); // SWS = SW
+    }
+
+    #[test]
+    fn freeze_hse_bypass_72MHz() {
+        mock::reset();
+        model_rcc_hardware();
+        let rcc = rcc::new();
+        let clocks = rcc
+            .freeze(ClockConfig::new().use_hse_bypass(8_000_000).sysclk(72_000_000))
+            .unwrap();
+        assert_eq!(clocks.source(), ClockSource::Hse(8_000_000, HseMode::Bypass));
+        let cr = mock::peek(RCC_CR);
+        assert_eq!(cr & ((1 << 16) | (1 << 18) | (1 << 19) | (1 << 24)), (1 << 16) | (1 << 18) | (1 << 19) | (1 << 24));
+        let cfgr = mock::peek(RCC_CFGR);
+        assert_eq!((cfgr >> 16) & 0b11, 0b01); // PLLSRC = HSE, PLLXTPRE = 0
+        assert_eq!((cfgr >> 18) & 0b1111, 9 - 2);
+        assert_eq!(cfgr & 0b11, 0b10); // SW = PLL
+        assert_eq!(rcc.read_clocks(), clocks);
+    }
+
+    #[test]
+    fn freeze_writes_cfgr_and_mco_before_enabling_css() {
+        mock::reset();
+        model_rcc_hardware();
+        mock::on_write(RCC_CFGR, |cfgr| {
+            assert_eq!(mock::peek(RCC_CR) & (1 << 19), 0, "CFGR written while CSS is on");
+            (cfgr & !(0b11 << 2)) | ((cfgr & 0b11) << 2)
+        });
+        mock::poke(RCC_CR, (1 << 16) | (1 << 17) | (1 << 18) | (1 << 19)); // 이전 freeze 로 CSS 동작 중
+        let config = ClockConfig::new().use_hse_bypass(8_000_000).sysclk(72_000_000).mco(Mco::PllDiv2);
+        rcc::new().freeze(config).unwrap();
+        assert_eq!((mock::peek(RCC_CFGR) >> 24) & 0b111, 0b111);
+        assert_eq!(mock::writes(Reg::new(RCC_CR).bit_band(19).addr()), [0, 1]);
+    }
+
+    #[test]
+    fn freeze_hsi_turns_css_and_hse_off() {
+        mock::reset();
+        model_rcc_hardware();
+        mock::poke(RCC_CR, (1 << 16) | (1 << 17) | (1 << 18) | (1 << 19)); // HSE bypass + CSS 동작 중
+        let clocks = rcc::new().freeze(ClockConfig::new().sysclk(64_000_000)).unwrap();
+        assert_eq!(clocks.source(), ClockSource::Hsi);
+        assert_eq!(mock::peek(RCC_CR) & ((1 << 16) | (1 << 18) | (1 << 19)), 0);
+    }
+
+    #[test]
+    fn freeze_gives_up_when_pll_never_locks() {
+        use crate::peripherals::dwt::DWT_BASE;
+        mock::reset();
+        // HSI 는 뜨지만 PLLRDY 는 오지 않음, CYCCNT 는 읽을 때마다 1000 cycle 진행
+        mock::on_write(RCC_CR, |cr| (cr & !(1 << 1)) | ((cr & 1) << 1));
+        mock::on_read(DWT_BASE + 0x04, |cyccnt| cyccnt.wrapping_add(1_000));
+        let rcc = rcc::new();
+        assert_eq!(rcc.freeze(ClockConfig::new().sysclk(64_000_000)), Err(ClockError::ReadyTimeout));
+        assert_eq!(mock::peek(RCC_CFGR) & 0b11, 0b00); // SW = HSI 에서 멈춤
+        assert_eq!(rcc.read_clocks().sysclk(), HSI_VALUE);
+    }
+
+    #[test]
+    fn css_recover_returns_running_clocks_when_hsi_never_ready() {
+        use crate::peripherals::dwt::DWT_BASE;
+        mock::reset();
+        mock::poke(RCC_BASE + 0x08, 1 << 7); // CSSF
+        mock::on_read(DWT_BASE + 0x04, |cyccnt| cyccnt.wrapping_add(1_000));
+        let clocks = rcc::new().css_recover(ClockConfig::new().use_hse_bypass(8_000_000).sysclk(72_000_000));
+        assert_eq!(clocks.map(|c| c.sysclk()), Some(HSI_VALUE));
+        assert_eq!(mock::peek(RCC_CFGR), 0); // SW / 분주기 는 건드리지 않음
+    }
+
+    #[test]
+    fn css_recover_ignores_other_nmi() {
+        mock::reset();
+        assert_eq!(rcc::new().css_recover(ClockConfig::new()), None);
+        assert_eq!(mock::peek(RCC_BASE + 0x08), 0);
+    }
+
+    #[test]
+    fn css_recover_clears_cssf_and_runs_on_hsi() {
+        mock::reset();
+        mock::poke(RCC_CR, 1 << 1); // HSIRDY; HSE / PLL 은 하드웨어가 이미 끔
+        mock::poke(RCC_BASE + 0x08, 1 << 7); // CSSF
+        let clocks = rcc::new().css_recover(ClockConfig::new().use_hse_bypass(8_000_000)).unwrap();
+        assert_eq!(clocks.source(), ClockSource::Hsi);
+        assert_eq!(clocks.sysclk(), HSI_VALUE);
+        assert_eq!(mock::peek(RCC_BASE + 0x08) & (1 << 23), 1 << 23); // CSSC
+    }
+
+    #[test]
+    fn reset_flags_decode_primary_cause() {
+        // POR 은 PINRSTF 도 같이 설정
+        assert_eq!(ResetFlags::from_csr((1 << 26) | (1 << 27)).cause(), ResetCause::PowerOn);
+        assert_eq!(ResetFlags::from_csr((1 << 26) | (1 << 28)).cause(), ResetCause::Software);
+        assert_eq!(ResetFlags::from_csr((1 << 26) | (1 << 29)).cause(), ResetCause::IndependentWatchdog);
+        assert_eq!(ResetFlags::from_csr(1 << 30).cause(), ResetCause::WindowWatchdog);
+        assert_eq!(ResetFlags::from_csr(1 << 31).cause(), ResetCause::LowPower);
+        assert_eq!(ResetFlags::from_csr(1 << 26).cause(), ResetCause::Pin);
+        assert_eq!(ResetFlags::from_csr(0b11).cause(), ResetCause::Unknown); // LSION / LSIRDY 무시
+    }
+
+    #[test]
+    fn take_reset_flags_sets_rmvf() {
+        mock::reset();
+        mock::poke(RCC_BASE + 0x24, (1 << 26) | (1 << 29) | 0b1);
+        let flags = rcc::new().take_reset_flags();
+        assert!(flags.contains(ResetFlags::IWDG));
+        assert!(flags.contains(ResetFlags::PIN));
+        assert!(!flags.contains(ResetFlags::POR));
+        assert_eq!(mock::peek(RCC_BASE + 0x24) & (1 << 24), 1 << 24);
+    }
+
+    #[test]
+    fn cfgr_mco_routes_clock_to_pa8() {
+        mock::reset();
+        let gpioa_crh = GpioXBase::A as u32 + 0x04;
+        mock::poke(gpioa_crh, 0x4444_4444);
+        let rcc = rcc::new();
+        let pa8 = Parts::<'A'>::new().p8.into_alternate_push_pull(Speed::Mhz50);
+        rcc.cfgr_mco(Mco::PllDiv2, &pa8);
+        assert_eq!((mock::peek(RCC_CFGR) >> 24) & 0b111, 0b111);
+        assert_eq!(mock::peek(gpioa_crh), 0x4444_444B);
+        rcc.cfgr_mco(Mco::NoClock, &pa8);
+        assert_eq!((mock::peek(RCC_CFGR) >> 24) & 0b111, 0);
+    }
+
+    #[test]
+    fn switch_clocks_notifies_dependents() {
+        use core::cell::Cell;
+        struct Recorder(Cell<u32>);
+        impl ClockDependent for Recorder {
+            fn clocks_changed(&self, clocks: &Clocks) {
+                self.0.set(clocks.pclk1());
             }
-            self.apb1enr.write_volatile(apb1enr_val);
         }
+
+        mock::reset();
+        model_rcc_hardware();
+        let (a, b) = (Recorder(Cell::new(0)), Recorder(Cell::new(0)));
+        let rcc = rcc::new();
+        rcc.switch_clocks(ClockConfig::new().sysclk(64_000_000), &[&a, &b]).unwrap();
+        assert_eq!((a.0.get(), b.0.get()), (32_000_000, 32_000_000));
+
+        rcc.switch_clocks(ClockConfig::new(), &[&a]).unwrap();
+        assert_eq!((a.0.get(), b.0.get()), (8_000_000, 32_000_000));
     }
 }
@@ -0,0 +1,278 @@
+//! ## register - 공용 레지스터 접근 계층
+//! 모든 peripheral 드라이버는 `*mut u32` 를 직접 만들지 않고 `Reg` 를 통해 레지스터에 접근한다. <br/>
+//! - 타겟 빌드: `read_volatile` / `write_volatile` 로 실제 메모리 맵 레지스터에 접근 <br/>
+//! - 호스트 `cargo test`: 주소별 in-memory 레지스터 파일 (`mock`) 에 접근 <br/>
+
+/// 32-bit memory-mapped register
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Reg {
+    addr: u32,
+}
+
+impl Reg {
+    pub const fn new(addr: u32) -> Reg {
+        Reg { addr }
+    }
+
+    /// base + offset 위치의 레지스터
+    pub const fn at(base: u32, offset: u32) -> Reg {
+        Reg { addr: base + offset }
+    }
+
+    pub const fn addr(&self) -> u32 {
+        self.addr
+    }
+
+    pub fn read(&self) -> u32 {
+        backend::read(self.addr)
+    }
+
+    pub fn write(&self, value: u32) {
+        backend::write(self.addr, value);
+    }
+
+    /// Read-modify-write
+    pub fn modify<F: FnOnce(u32) -> u32>(&self, f: F) {
+        let value = self.read();
+        self.write(f(value));
+    }
+
+    /// mask 에 해당하는 비트를 1 로 설정 (read-modify-write)
+    pub fn set_bits(&self, mask: u32) {
+        self.modify(|v| v | mask);
+    }
+
+    /// mask 에 해당하는 비트를 0 으로 클리어 (read-modify-write)
+    pub fn clear_bits(&self, mask: u32) {
+        self.modify(|v| v & !mask);
+    }
+
+    /// 단일 비트를 set(true) / clear(false)
+    pub fn set_bit(&self, bit: u8, value: bool) {
+        if value {
+            self.set_bits(1 << bit);
+        } else {
+            self.clear_bits(1 << bit);
+        }
+    }
+
+    pub fn read_bit(&self, bit: u8) -> bool {
+        self.read() & (1 << bit) != 0
+    }
+
+    /// ## write_field
+    /// `mask` 는 shift 하기 전의 필드 폭 마스크 (ex. 4-bit 필드 = `0b1111`) <br/>
+    /// 필드를 클리어한 뒤 `value` 를 기록한다. `value` 가 필드 폭을 넘으면 잘린다.
+    pub fn write_field(&self, shift: u8, mask: u32, value: u32) {
+        self.modify(|v| (v & !(mask << shift)) | ((value & mask) << shift));
+    }
+
+    pub fn read_field(&self, shift: u8, mask: u32) -> u32 {
+        (self.read() >> shift) & mask
+    }
+
+    /// ## write_u8
+    /// byte 단위 쓰기. NVIC IPR 처럼 byte access 를 지원하는 레지스터에만 사용한다. <br/>
+    /// 같은 word 의 나머지 byte 를 건드리지 않으므로 read-modify-write 가 필요 없다.
+    pub fn write_u8(&self, value: u8) {
+        backend::write_u8(self.addr, value);
+    }
+}
+
+#[cfg(not(test))]
+mod backend {
+    #[inline(always)]
+    pub fn read(addr: u32) -> u32 {
+        unsafe { (addr as *const u32).read_volatile() }
+    }
+
+    #[inline(always)]
+    pub fn write(addr: u32, value: u32) {
+        unsafe { (addr as *mut u32).write_volatile(value) }
+    }
+
+    #[inline(always)]
+    pub fn write_u8(addr: u32, value: u8) {
+        unsafe { (addr as *mut u8).write_volatile(value) }
+    }
+}
+
+#[cfg(test)]
+use mock as backend;
+
+/// ## mock - 호스트 테스트용 in-memory 레지스터 파일
+/// 테스트 스레드마다 독립된 레지스터 파일을 가진다. 한 번도 쓰지 않은 주소는 0 을 읽는다. <br/>
+/// 하드웨어가 세우는 플래그 (HSIRDY, PLLRDY 등) 는 테스트에서 `poke` 로 미리 설정하거나 <br/>
+/// `on_write` / `on_read` hook 으로 흉내 낸다.
+#[cfg(test)]
+pub mod mock {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    use crate::peripherals::bitband;
+
+    std::thread_local! {
+        static REGISTERS: RefCell<BTreeMap<u32, u32>> = RefCell::new(BTreeMap::new());
+        static WRITE_HOOKS: RefCell<BTreeMap<u32, fn(u32) -> u32>> = RefCell::new(BTreeMap::new());
+        static READ_HOOKS: RefCell<BTreeMap<u32, fn(u32) -> u32>> = RefCell::new(BTreeMap::new());
+        static WRITE_LOG: RefCell<BTreeMap<u32, Vec<u32>>> = RefCell::new(BTreeMap::new());
+    }
+
+    /// bit-band alias 주소는 대상 레지스터의 비트를 읽는다. `on_read` hook 이 있으면 hook 을 거친 값을 저장하고 돌려준다.
+    pub fn read(addr: u32) -> u32 {
+        if let Some((word, bit)) = bitband::target(addr) {
+            return (read(word) >> bit) & 1;
+        }
+        let value = REGISTERS.with(|r| r.borrow().get(&addr).copied().unwrap_or(0));
+        match READ_HOOKS.with(|h| h.borrow().get(&addr).copied()) {
+            Some(hook) => {
+                let value = hook(value);
+                poke(addr, value);
+                value
+            }
+            None => value,
+        }
+    }
+
+    /// 드라이버의 쓰기. `on_write` hook 이 있으면 hook 을 거친 값이 저장된다. <br/>
+    /// bit-band alias 쓰기는 alias 주소에 기록을 남기고 대상 레지스터의 비트만 바꾼다 (대상 레지스터의 hook 적용).
+    pub fn write(addr: u32, value: u32) {
+        WRITE_LOG.with(|l| l.borrow_mut().entry(addr).or_default().push(value));
+        if let Some((word, bit)) = bitband::target(addr) {
+            let current = read(word) & !(1 << bit);
+            let value = current | ((value & 1) << bit);
+            let hook = WRITE_HOOKS.with(|h| h.borrow().get(&word).copied());
+            poke(word, hook.map_or(value, |f| f(value)));
+            return;
+        }
+        let hook = WRITE_HOOKS.with(|h| h.borrow().get(&addr).copied());
+        let value = hook.map_or(value, |f| f(value));
+        poke(addr, value);
+    }
+
+    /// byte 쓰기는 byte 주소에 기록을 남기고 word 의 해당 byte 만 바꾼다 (word 의 `on_write` hook 적용). <br/>
+    /// 하드웨어처럼 word 를 읽지 않으므로 `on_read` hook 은 거치지 않는다.
+    pub fn write_u8(addr: u32, value: u8) {
+        WRITE_LOG.with(|l| l.borrow_mut().entry(addr).or_default().push(value as u32));
+        let (word, shift) = (addr & !3, (addr & 3) * 8);
+        let current = REGISTERS.with(|r| r.borrow().get(&word).copied().unwrap_or(0));
+        let value = (current & !(0xFF << shift)) | ((value as u32) << shift);
+        let hook = WRITE_HOOKS.with(|h| h.borrow().get(&word).copied());
+        poke(word, hook.map_or(value, |f| f(value)));
+    }
+
+    /// 레지스터 값 확인 (`on_read` hook 을 거치지 않는다)
+    pub fn peek(addr: u32) -> u32 {
+        if let Some((word, bit)) = bitband::target(addr) {
+            return (peek(word) >> bit) & 1;
+        }
+        REGISTERS.with(|r| r.borrow().get(&addr).copied().unwrap_or(0))
+    }
+
+    /// 드라이버가 `addr` 에 쓴 값들 (순서대로, hook 적용 전). pulse / write-1-to-clear 확인용
+    pub fn writes(addr: u32) -> Vec<u32> {
+        WRITE_LOG.with(|l| l.borrow().get(&addr).cloned().unwrap_or_default())
+    }
+
+    /// 하드웨어 상태 흉내 (reset 값, 상태 플래그 등). hook 을 거치지 않는다.
+    pub fn poke(addr: u32, value: u32) {
+        REGISTERS.with(|r| {
+            r.borrow_mut().insert(addr, value);
+        });
+    }
+
+    /// ## on_write
+    /// 쓰기 값에 하드웨어 반응을 반영하는 hook (ex. HSEON 을 쓰면 HSERDY 도 set). <br/>
+    /// `reset` 으로 함께 지워진다.
+    pub fn on_write(addr: u32, hook: fn(u32) -> u32) {
+        WRITE_HOOKS.with(|h| {
+            h.borrow_mut().insert(addr, hook);
+        });
+    }
+
+    /// ## on_read
+    /// 읽을 때마다 값이 바뀌는 레지스터 (free-running counter 등) 흉내. `reset` 으로 함께 지워진다.
+    pub fn on_read(addr: u32, hook: fn(u32) -> u32) {
+        READ_HOOKS.with(|h| {
+            h.borrow_mut().insert(addr, hook);
+        });
+    }
+
+    /// 현재 스레드의 레지스터 파일, hook, 쓰기 기록 초기화
+    pub fn reset() {
+        REGISTERS.with(|r| r.borrow_mut().clear());
+        WRITE_HOOKS.with(|h| h.borrow_mut().clear());
+        READ_HOOKS.with(|h| h.borrow_mut().clear());
+        WRITE_LOG.with(|l| l.borrow_mut().clear());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_field_clears_only_the_field() {
+        mock::reset();
+        let reg = Reg::new(0x1000);
+        reg.write(0xFFFF_FFFF);
+        reg.write_field(8, 0b1111, 0b0101);
+        assert_eq!(reg.read(), 0xFFFF_F5FF);
+        assert_eq!(reg.read_field(8, 0b1111), 0b0101);
+    }
+
+    #[test]
+    fn write_field_truncates_value_to_mask() {
+        mock::reset();
+        let reg = Reg::new(0x1000);
+        reg.write_field(4, 0b11, 0b111);
+        assert_eq!(reg.read(), 0b11 << 4);
+    }
+
+    #[test]
+    fn set_bit_and_read_bit() {
+        mock::reset();
+        let reg = Reg::at(0x2000, 0x04);
+        assert_eq!(reg.addr(), 0x2004);
+        reg.set_bit(3, true);
+        reg.set_bit(5, true);
+        assert!(reg.read_bit(3));
+        reg.set_bit(3, false);
+        assert!(!reg.read_bit(3));
+        assert_eq!(mock::peek(0x2004), 1 << 5);
+        assert_eq!(mock::writes(0x2004), [1 << 3, (1 << 3) | (1 << 5), 1 << 5]);
+    }
+
+    #[test]
+    fn on_write_hook_models_hardware_flags() {
+        mock::reset();
+        // bit 0 을 쓰면 bit 1 (ready) 이 따라 올라가는 레지스터
+        mock::on_write(0x3000, |v| if v & 1 != 0 { v | 0b10 } else { v & !0b10 });
+        let reg = Reg::new(0x3000);
+        reg.set_bit(0, true);
+        assert!(reg.read_bit(1));
+        mock::poke(0x3000, 0b1);
+        assert_eq!(reg.read(), 0b1);
+    }
+
+    #[test]
+    fn on_read_hook_models_counter() {
+        mock::reset();
+        mock::on_read(0x3004, |v| v + 10);
+        let reg = Reg::new(0x3004);
+        assert_eq!(reg.read(), 10);
+        assert_eq!(reg.read(), 20);
+        assert_eq!(mock::peek(0x3004), 20);
+    }
+
+    #[test]
+    fn write_u8_touches_only_its_byte() {
+        mock::reset();
+        mock::poke(0x4000, 0xAAAA_AAAA);
+        let reg = Reg::at(0x4000, 0x02);
+        reg.write_u8(0x5C);
+        assert_eq!(mock::peek(0x4000), 0xAA5C_AAAA);
+        assert_eq!(mock::writes(0x4002), [0x5C]);
+        assert!(mock::writes(0x4000).is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+#![allow(non_snake_case)]
+
+use crate::peripherals::register::Reg;
+
+pub const SYSTICK_BASE: u32 = 0xE000_E010;
+
+/// RVR 는 24-bit
+pub const SYSTICK_MAX_RELOAD: u32 = 0x00FF_FFFF;
+
+/// ## SysTick - Cortex-M3 system timer
+/// CLKSOURCE=1 (processor clock = HCLK) 로 사용한다. <br/>
+/// 다운카운터가 0 에 도달하면 COUNTFLAG 가 set 되고, CSR 을 읽으면 클리어된다.
+pub struct SysTick {
+    base: u32,
+}
+
+impl SysTick {
+    pub(super) fn new() -> SysTick {
+        SysTick { base: SYSTICK_BASE }
+    }
+
+    fn CSR(&self) -> Reg {
+        Reg::at(self.base, 0x00)
+    }
+
+    fn RVR(&self) -> Reg {
+        Reg::at(self.base, 0x04)
+    }
+
+    fn CVR(&self) -> Reg {
+        Reg::at(self.base, 0x08)
+    }
+
+    /// ## csr_enable
+    /// ENABLE (bit 0) 만 바꾸고 켤 때 CLKSOURCE (bit 2) = processor clock. TICKINT (bit 1) 는 유지한다. <br/>
+    /// COUNTFLAG 는 read-only 이므로 다시 쓰지 않는다 (read-modify-write 의 읽기로 클리어됨).
+    pub fn csr_enable(&self, enable: bool) {
+        self.CSR().modify(|csr| {
+            let csr = csr & 0b111;
+            if enable { csr | 0b101 } else { csr & !0b001 }
+        });
+    }
+
+    /// COUNTFLAG (bit 16), 읽으면 클리어
+    pub fn read_csr_countflag(&self) -> bool {
+        self.CSR().read_bit(16)
+    }
+
+    /// reload 값 (1..=0xFF_FFFF). 주기는 reload + 1 tick
+    pub fn rvr_set(&self, reload: u32) {
+        self.RVR().write(reload & SYSTICK_MAX_RELOAD);
+    }
+
+    /// CVR 에 아무 값이나 쓰면 0 으로 클리어되고 COUNTFLAG 도 클리어된다
+    pub fn cvr_clear(&self) {
+        self.CVR().write(0);
+    }
+
+    /// ## wait_ticks
+    /// HCLK 기준 `ticks` 만큼 대기 (busy wait). 24-bit 를 넘으면 여러 번 나누어 센다.
+    pub fn wait_ticks(&self, ticks: u64) {
+        let mut remaining = ticks;
+        while remaining > 0 {
+            let chunk = remaining.min(SYSTICK_MAX_RELOAD as u64) as u32;
+            // reload 0 은 카운터가 멈추므로 최소 1 tick 주기로 맞춘다
+            self.rvr_set(chunk.max(2) - 1);
+            self.cvr_clear();
+            self.csr_enable(true);
+            while !self.read_csr_countflag() {}
+            self.csr_enable(false);
+            remaining -= chunk as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+    #[test]
+    fn wait_ticks_splits_into_24bit_chunks() {
+        mock::reset();
+        // ENABLE 를 쓰면 바로 COUNTFLAG 가 올라간 것으로 흉내
+        mock::on_write(SYSTICK_BASE, |v| if v & 1 != 0 { v | (1 << 16) } else { v });
+        let systick = SysTick::new();
+        systick.wait_ticks(2 * SYSTICK_MAX_RELOAD as u64 + 1000);
+        assert_eq!(
+            mock::writes(SYSTICK_BASE + 0x04),
+            [SYSTICK_MAX_RELOAD - 1, SYSTICK_MAX_RELOAD - 1, 999]
+        );
+        assert_eq!(mock::writes(SYSTICK_BASE), [0b101, 0b100, 0b101, 0b100, 0b101, 0b100]);
+    }
+
+    #[test]
+    fn csr_enable_keeps_tickint() {
+        mock::reset();
+        mock::poke(SYSTICK_BASE, (1 << 16) | 0b010); // COUNTFLAG, TICKINT
+        let systick = SysTick::new();
+        systick.csr_enable(true);
+        systick.csr_enable(false);
+        assert_eq!(mock::writes(SYSTICK_BASE), [0b111, 0b110]);
+    }
+}
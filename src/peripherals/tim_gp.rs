@@ -1,3 +1,8 @@
+use crate::peripherals::{
+    rcc::{ClockDependent, Clocks},
+    register::Reg,
+};
+
 pub enum TIM_GP_TYPE {
     TIM2 = 0x4000_0000,
     TIM5 = 0x4000_0C00 
@@ -5,35 +10,58 @@ pub enum TIM_GP_TYPE {
 
 const TIM2_BASE: u32 = 0x4000_0000;
 
+/// `start_tick` 의 counter clock
+const TICK_COUNTER_HZ: u32 = 1_000_000;
+
+/// SR 의 rc_w0 flag: UIF, CC1IF..CC4IF (4:0), TIF (6), CC1OF..CC4OF (12:9)
+const SR_RC_W0_MASK: u32 = 0x1E5F;
+
+/// ## TickError
+/// `start_tick` 에 준 timer clock 과 주기로 update 이벤트를 만들 수 없는 경우
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TickError {
+    /// TIMCLK < 1 MHz: PSC 로 1 MHz counter 를 만들 수 없음
+    TimerClockTooLow,
+    /// `hz` 는 16 Hz (ARR 16-bit) ~ 500 kHz (ARR >= 1)
+    RateOutOfRange,
+}
+
+/// PSC: TIMCLK → 1 MHz counter
+const fn tick_psc(timclk: u32) -> Result<u32, TickError> {
+    if timclk < TICK_COUNTER_HZ {
+        return Err(TickError::TimerClockTooLow);
+    }
+    Ok(timclk / TICK_COUNTER_HZ - 1)
+}
+
+/// ARR: 1 MHz counter 가 `hz` 마다 overflow. ARR 0 이면 counter 가 멈춘다
+const fn tick_arr(hz: u32) -> Result<u32, TickError> {
+    if hz == 0 || TICK_COUNTER_HZ / hz < 2 || TICK_COUNTER_HZ / hz - 1 > 0xFFFF {
+        return Err(TickError::RateOutOfRange);
+    }
+    Ok(TICK_COUNTER_HZ / hz - 1)
+}
+
 pub struct TIM_GP{
     base: u32,
 }
 
 impl TIM_GP {
-    pub fn new(tim_gp: TIM_GP_TYPE) -> Self {
+    pub(super) fn new(tim_gp: TIM_GP_TYPE) -> Self {
         TIM_GP { base: tim_gp as u32 }
     }
 
-    unsafe fn CR1(&self) -> *mut u32 {
-        (self.base + 0x00) as *mut u32
+    fn CR1(&self) -> Reg {
+        Reg::at(self.base, 0x00)
     }
-    unsafe fn PSC(&self) -> *mut u32 {
-        (self.base + 0x28) as *mut u32
+    fn PSC(&self) -> Reg {
+        Reg::at(self.base, 0x28)
     }
     pub fn cr1_cen_set(&self, enable: bool) {
-        unsafe {
-            let mut cr1_val = self.CR1().read_volatile();
-            if enable {
-                cr1_val |=  (0b1 << 0)
-            } else {
-                cr1_val &= !(0b1 << 0)
-            }
-        }
+        self.CR1().set_bit(0, enable);
     }
     pub fn set_psc (&self, psc_value: u32) {
-        unsafe {
-            self.PSC().write_volatile(psc_value);
-        }
+        self.PSC().write(psc_value);
     }
     
 
@@ -44,39 +72,116 @@ pub struct TIM2 {
 }
 
 impl TIM2 {
-    pub fn new() -> TIM2 {
+    pub(super) fn new() -> TIM2 {
         TIM2 { base: TIM2_BASE }
     }
 
-    fn CR1(&self) -> *mut u32 {
-        (self.base + 0x00) as *mut u32
+    fn CR1(&self) -> Reg {
+        Reg::at(self.base, 0x00)
+    }
+
+    fn PSC(&self) -> Reg {
+        Reg::at(self.base, 0x28)
+    }
+
+    fn ARR(&self) -> Reg {
+        Reg::at(self.base, 0x2C)
+    }
+
+    fn DIER(&self) -> Reg {
+        Reg::at(self.base, 0x0C)
+    }
+
+    fn SR(&self) -> Reg {
+        Reg::at(self.base, 0x10)
+    }
+
+    fn EGR(&self) -> Reg {
+        Reg::at(self.base, 0x14)
     }
 
-    fn PSC(&self) -> *mut u32 {
-        (self.base + 0x28) as *mut u32
+    fn CNT(&self) -> Reg {
+        Reg::at(self.base, 0x24)
     }
 
-    fn ARR(&self) -> *mut u32 {
-        (self.base + 0x2C) as *mut u32
+    pub fn init(&self, psc: u32, arr: u32) {
+        self.PSC().write(psc);
+        self.ARR().write(arr);
+        self.CR1().write(0x1); // Enable TIM2
     }
 
-    fn SR(&self) -> *mut u32 {
-        (self.base + 0x10) as *mut u32
+    /// ## start_tick - 주기 update 인터럽트
+    /// counter 를 1 MHz 로 돌리고 (PSC = TIMCLK / 1 MHz - 1) `hz` 마다 update 이벤트 (UIE) 를 낸다. <br/>
+    /// `timclk` 는 `Clocks::pclk1_tim()`. NVIC 의 `Interrupt::TIM2` 는 호출자가 enable 한다. <br/>
+    /// 만들 수 없는 조합이면 레지스터를 건드리지 않고 에러.
+    pub fn start_tick(&self, timclk: u32, hz: u32) -> Result<(), TickError> {
+        let psc = tick_psc(timclk)?;
+        let arr = tick_arr(hz)?;
+        self.CR1().write(0);
+        self.PSC().write(psc);
+        self.ARR().write(arr);
+        self.EGR().write(1); // UG: PSC 는 preload 이므로 update 이벤트로 바로 적용 (counter 도 0)
+        self.SR().write(0); // UG 로 생긴 flag 정리
+        self.DIER().write(1); // UIE
+        self.CR1().write(0x1); // CEN
+        Ok(())
     }
 
-    fn CNT(&self) -> *mut u32 {
-        (self.base + 0x24) as *mut u32
+    /// SR 은 rc_w0: UIF 만 0 을 쓰고 나머지 flag 는 1 (영향 없음). <br/>
+    /// reserved 비트는 0 으로 유지한다 (RM0008 15.4.5).
+    pub fn sr_uif_clear(&self) {
+        self.SR().write(!1 & SR_RC_W0_MASK);
     }
 
-    pub unsafe fn init(&self, psc: u32, arr: u32) {
-        self.PSC().write_volatile(psc);
-        self.ARR().write_volatile(arr);
-        self.CR1().write_volatile(0x1); // Enable TIM2
+    pub fn sr_uif(&self) -> bool {
+        self.SR().read_bit(0)
     }
 
-    pub unsafe fn delay_ms(&self, ms: u32) {
+    pub fn delay_ms(&self, ms: u32) {
         let target = ms; // Convert ms to us
-        self.CNT().write_volatile(0); // Reset counter
-        while self.CNT().read_volatile() < target {}
+        self.CNT().write(0); // Reset counter
+        while self.CNT().read() < target {}
+    }
+}
+
+/// 클럭이 바뀌어도 counter 가 1 MHz 를 유지하도록 PSC 만 다시 계산한다 (tick 주기 유지, 다음 update 부터 적용). <br/>
+/// TIMCLK 가 1 MHz 미만이면 PSC 를 그대로 둔다.
+impl ClockDependent for TIM2 {
+    fn clocks_changed(&self, clocks: &Clocks) {
+        if let Ok(psc) = tick_psc(clocks.pclk1_tim()) {
+            self.PSC().write(psc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+
+    #[test]
+    fn start_tick_1khz_from_72MHz() {
+        mock::reset();
+        let tim2 = TIM2::new();
+        tim2.start_tick(72_000_000, 1_000).unwrap();
+        assert_eq!(mock::peek(TIM2_BASE + 0x28), 71);
+        assert_eq!(mock::peek(TIM2_BASE + 0x2C), 999);
+        assert_eq!(mock::writes(TIM2_BASE + 0x14), [1]); // EGR UG
+        assert_eq!(mock::peek(TIM2_BASE + 0x0C), 1);
+        assert_eq!(mock::peek(TIM2_BASE), 1);
+        tim2.sr_uif_clear();
+        assert_eq!(mock::writes(TIM2_BASE + 0x10).last(), Some(&0x1E5E)); // CC1..4OF, TIF, CC1..4IF 만 1
+    }
+    #[test]
+    fn start_tick_rejects_impossible_rates() {
+        mock::reset();
+        let tim2 = TIM2::new();
+        assert_eq!(tim2.start_tick(500_000, 1_000), Err(TickError::TimerClockTooLow));
+        assert_eq!(tim2.start_tick(8_000_000, 0), Err(TickError::RateOutOfRange));
+        assert_eq!(tim2.start_tick(8_000_000, 1_000_000), Err(TickError::RateOutOfRange));
+        assert_eq!(tim2.start_tick(8_000_000, 15), Err(TickError::RateOutOfRange)); // ARR 66665
+        assert!(mock::writes(TIM2_BASE).is_empty());
+        assert_eq!(tim2.start_tick(8_000_000, 16), Ok(()));
+        assert_eq!(mock::peek(TIM2_BASE + 0x2C), 62_499);
     }
 }
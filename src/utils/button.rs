@@ -0,0 +1,318 @@
+//! ## button - debounced push button
+//! EXTI edge 가 들어오면 settle window 를 다시 시작하고, 주기 tick (1 ms timer) 에서 <br/>
+//! window 가 지난 뒤의 핀 레벨만 상태 변화로 인정한다. 채터링으로 edge 가 여러 번 들어와도 이벤트는 한 번. <br/>
+//! 이벤트는 `EventQueue` 에 쌓이고 main loop 가 꺼내 처리한다.
+//! ```ignore
+//! static B1: Shared<Button<'C', 13, PullUp>> = Shared::new();
+//! static EVENTS: EventQueue<ButtonEvent, 8> = EventQueue::new();
+//!
+//! fn on_b1_edge() { B1.with(|b| b.edge(now_ms())); }          // EXTI callback (Edge::Both)
+//! fn on_tick() { B1.with(|b| b.update(now_ms(), &EVENTS)); }  // 1 ms timer
+//!
+//! while let Some(event) = EVENTS.pop() { ... }                // main loop
+//! ```
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::peripherals::gpio::{Input, Pin};
+
+/// 채터링이 가라앉을 때까지 기다리는 시간
+pub const SETTLE_MS: u32 = 20;
+/// 이 시간 이상 누르고 있으면 `LongPress`
+pub const LONG_PRESS_MS: u32 = 800;
+/// 앞선 press 로부터 이 시간 안에 다시 누르면 `DoubleClick`
+pub const DOUBLE_CLICK_MS: u32 = 400;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ButtonEventKind {
+    Press,
+    Release,
+    /// 누른 상태로 `LONG_PRESS_MS` 경과 (press 당 한 번)
+    LongPress,
+    /// 두 번째 `Press` 직후에 추가로 발생
+    DoubleClick,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ButtonEvent {
+    /// `Button::new` 에 준 id. 여러 버튼이 같은 queue 를 쓸 때 구분용
+    pub id: u8,
+    pub kind: ButtonEventKind,
+}
+
+/// ## Debouncer - 핀과 무관한 상태 기계
+/// 시간은 ms 단위 free-running counter (wrap-around 허용).
+pub struct Debouncer {
+    settle_ms: u32,
+    long_press_ms: u32,
+    double_click_ms: u32,
+    pressed: bool,
+    settling: bool,
+    last_edge: u32,
+    pressed_at: u32,
+    long_reported: bool,
+    last_press: Option<u32>,
+}
+
+impl Debouncer {
+    pub const fn new() -> Debouncer {
+        Debouncer::with_timing(SETTLE_MS, LONG_PRESS_MS, DOUBLE_CLICK_MS)
+    }
+
+    pub const fn with_timing(settle_ms: u32, long_press_ms: u32, double_click_ms: u32) -> Debouncer {
+        Debouncer {
+            settle_ms,
+            long_press_ms,
+            double_click_ms,
+            pressed: false,
+            settling: false,
+            last_edge: 0,
+            pressed_at: 0,
+            long_reported: false,
+            last_press: None,
+        }
+    }
+
+    /// debounce 된 현재 상태
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// EXTI edge: settle window 재시작
+    pub fn edge(&mut self, now: u32) {
+        self.settling = true;
+        self.last_edge = now;
+    }
+
+    /// ## update
+    /// tick 마다 호출. `raw_pressed` 는 지금 읽은 핀 레벨 (눌림 = true).
+    pub fn update(&mut self, now: u32, raw_pressed: bool, mut emit: impl FnMut(ButtonEventKind)) {
+        if self.settling && now.wrapping_sub(self.last_edge) >= self.settle_ms {
+            self.settling = false;
+            if raw_pressed != self.pressed {
+                self.pressed = raw_pressed;
+                if raw_pressed {
+                    emit(ButtonEventKind::Press);
+                    self.pressed_at = now;
+                    self.long_reported = false;
+                    match self.last_press {
+                        Some(t) if now.wrapping_sub(t) <= self.double_click_ms => {
+                            emit(ButtonEventKind::DoubleClick);
+                            self.last_press = None;
+                        }
+                        _ => self.last_press = Some(now),
+                    }
+                } else {
+                    emit(ButtonEventKind::Release);
+                }
+            }
+        }
+        if self.pressed && !self.long_reported && now.wrapping_sub(self.pressed_at) >= self.long_press_ms {
+            emit(ButtonEventKind::LongPress);
+            self.long_reported = true;
+            self.last_press = None; // long press 뒤의 press 는 double click 이 아님
+        }
+    }
+}
+
+/// ## Button - 입력 핀 + Debouncer
+/// `active_low`: 눌렀을 때 LOW 인 버튼 (Nucleo B1, pull-up 스위치)
+pub struct Button<const P: char, const N: u8, PULL> {
+    id: u8,
+    pin: Pin<P, N, Input<PULL>>,
+    active_low: bool,
+    debouncer: Debouncer,
+}
+
+impl<const P: char, const N: u8, PULL> Button<P, N, PULL> {
+    pub fn new(id: u8, pin: Pin<P, N, Input<PULL>>, active_low: bool) -> Self {
+        Button {
+            id,
+            pin,
+            active_low,
+            debouncer: Debouncer::new(),
+        }
+    }
+
+    pub fn with_debouncer(mut self, debouncer: Debouncer) -> Self {
+        self.debouncer = debouncer;
+        self
+    }
+
+    pub fn edge(&mut self, now: u32) {
+        self.debouncer.edge(now);
+    }
+
+    pub fn update<const Q: usize>(&mut self, now: u32, queue: &EventQueue<ButtonEvent, Q>) {
+        let raw_pressed = self.pin.is_low() == self.active_low;
+        let id = self.id;
+        self.debouncer.update(now, raw_pressed, |kind| {
+            queue.push(ButtonEvent { id, kind });
+        });
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.debouncer.is_pressed()
+    }
+
+    /// 핀을 돌려받는다
+    pub fn release(self) -> Pin<P, N, Input<PULL>> {
+        self.pin
+    }
+}
+
+struct Ring<T, const N: usize> {
+    buf: [Option<T>; N],
+    head: usize,
+    len: usize,
+}
+
+/// ## EventQueue - 인터럽트 → main 단방향 고정 크기 queue
+/// push / pop 모두 critical section 안에서 실행된다. 가득 차면 새 이벤트를 버리고 `false`.
+pub struct EventQueue<T, const N: usize> {
+    ring: Mutex<RefCell<Ring<T, N>>>,
+}
+
+impl<T: Copy, const N: usize> EventQueue<T, N> {
+    pub const fn new() -> Self {
+        EventQueue {
+            ring: Mutex::new(RefCell::new(Ring {
+                buf: [const { None }; N],
+                head: 0,
+                len: 0,
+            })),
+        }
+    }
+
+    pub fn push(&self, value: T) -> bool {
+        critical_section::with(|cs| {
+            let mut ring = self.ring.borrow_ref_mut(cs);
+            if ring.len == N {
+                return false;
+            }
+            let tail = (ring.head + ring.len) % N;
+            ring.buf[tail] = Some(value);
+            ring.len += 1;
+            true
+        })
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        critical_section::with(|cs| {
+            let mut ring = self.ring.borrow_ref_mut(cs);
+            if ring.len == 0 {
+                return None;
+            }
+            let head = ring.head;
+            let value = ring.buf[head].take();
+            ring.head = (head + 1) % N;
+            ring.len -= 1;
+            value
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(debouncer: &mut Debouncer, now: u32, raw: bool) -> Vec<ButtonEventKind> {
+        let mut events = Vec::new();
+        debouncer.update(now, raw, |e| events.push(e));
+        events
+    }
+
+    #[test]
+    fn bouncing_edges_give_one_press_and_one_release() {
+        use ButtonEventKind::*;
+        let mut d = Debouncer::new();
+        // 0..6 ms 채터링
+        for t in 0..6 {
+            d.edge(t);
+            assert!(run(&mut d, t, t % 2 == 0).is_empty());
+        }
+        assert!(run(&mut d, 20, true).is_empty()); // 마지막 edge (5 ms) 로부터 15 ms
+        assert_eq!(run(&mut d, 25, true), [Press]);
+        assert!(run(&mut d, 26, true).is_empty());
+        d.edge(100);
+        d.edge(103);
+        assert_eq!(run(&mut d, 123, false), [Release]);
+    }
+
+    #[test]
+    fn glitch_shorter_than_settle_window_is_ignored() {
+        let mut d = Debouncer::new();
+        d.edge(0);
+        d.edge(2); // 눌렸다가 바로 원래대로
+        assert!(run(&mut d, 30, false).is_empty());
+        assert!(!d.is_pressed());
+    }
+
+    #[test]
+    fn long_press_reported_once() {
+        use ButtonEventKind::*;
+        let mut d = Debouncer::new();
+        d.edge(0);
+        assert_eq!(run(&mut d, 20, true), [Press]);
+        assert!(run(&mut d, 500, true).is_empty());
+        assert_eq!(run(&mut d, 820, true), [LongPress]);
+        assert!(run(&mut d, 2000, true).is_empty());
+        // long press 직후의 press 는 double click 이 아님
+        d.edge(2000);
+        assert_eq!(run(&mut d, 2020, false), [Release]);
+        d.edge(2100);
+        assert_eq!(run(&mut d, 2120, true), [Press]);
+    }
+
+    #[test]
+    fn double_click_within_window() {
+        use ButtonEventKind::*;
+        let mut d = Debouncer::new();
+        d.edge(0);
+        assert_eq!(run(&mut d, 20, true), [Press]);
+        d.edge(100);
+        assert_eq!(run(&mut d, 120, false), [Release]);
+        d.edge(200);
+        assert_eq!(run(&mut d, 220, true), [Press, DoubleClick]);
+        d.edge(300);
+        assert_eq!(run(&mut d, 320, false), [Release]);
+        // 세 번째 press 는 새 click 으로 시작
+        d.edge(400);
+        assert_eq!(run(&mut d, 420, true), [Press]);
+    }
+
+    #[test]
+    fn wraps_around_tick_counter() {
+        use ButtonEventKind::*;
+        let mut d = Debouncer::new();
+        d.edge(u32::MAX - 5);
+        assert_eq!(run(&mut d, 15, true), [Press]);
+    }
+
+    #[test]
+    fn event_queue_fifo_and_full() {
+        let queue: EventQueue<u8, 3> = EventQueue::new();
+        assert!(queue.push(1));
+        assert!(queue.push(2));
+        assert_eq!(queue.pop(), Some(1));
+        assert!(queue.push(3));
+        assert!(queue.push(4));
+        assert!(!queue.push(5));
+        assert_eq!((queue.pop(), queue.pop(), queue.pop(), queue.pop()), (Some(2), Some(3), Some(4), None));
+    }
+
+    #[test]
+    fn button_reads_active_low_pin() {
+        use crate::peripherals::{gpio::GpioXBase, register::mock, Peripherals};
+        mock::reset();
+        let pin = unsafe { Peripherals::steal() }.gpioc.p13.into_pull_up_input();
+        let mut button = Button::new(1, pin, true);
+        let queue: EventQueue<ButtonEvent, 4> = EventQueue::new();
+        mock::poke(GpioXBase::C as u32 + 0x08, 0); // IDR13 = 0 → 눌림
+        button.edge(0);
+        button.update(20, &queue);
+        assert_eq!(queue.pop(), Some(ButtonEvent { id: 1, kind: ButtonEventKind::Press }));
+        assert!(button.is_pressed());
+    }
+}
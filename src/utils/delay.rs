@@ -1,15 +1,131 @@
+use core::cell::Cell;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::peripherals::{
+    rcc::{ClockDependent, Clocks},
+    systick::SysTick,
+};
+
+#[cfg(not(test))]
 use cortex_m::asm::nop;
 
+/// 호스트 테스트에서는 지연 없이 바로 반환
+#[cfg(test)]
+fn nop() {}
+
+/// 지연 루프 1회에 걸리는 대략의 cycle 수 (nop 호출 + 분기, flash wait state 포함) <br/>
+/// 32 MHz 에서 1 ms = 3200 회
+const CYCLES_PER_LOOP: u32 = 10;
+
+/// 현재 SYSCLK. reset 직후에는 HSI 8 MHz 로 동작한다.
+static SYS_CLK_HZ: AtomicU32 = AtomicU32::new(8_000_000);
+
+/// ## set_sys_clk
+/// 클럭 변경 후 지연 루프 횟수를 다시 계산하도록 SYSCLK 를 알린다. <br/>
+/// `rcc::freeze` 가 호출하므로 직접 부를 일은 거의 없다.
+pub fn set_sys_clk(hz: u32) {
+    SYS_CLK_HZ.store(hz, Ordering::Relaxed);
+}
+
+pub fn sys_clk() -> u32 {
+    SYS_CLK_HZ.load(Ordering::Relaxed)
+}
+
 pub fn delay_sys_clk_ms(ms: u32) {
     // 클럭 주파수 및 지연 루프 보정
-    for _ in 0..100 * 8 * 4 * ms {
+    for _ in 0..sys_clk() / 1_000 / CYCLES_PER_LOOP * ms {
         nop();
     }
 }
 
 pub fn delay_sys_clk_10us(us_10: u32) {
     // 클럭 주파수 및 지연 루프 보정
-    for _ in 0.. 8 * 4 * us_10 {
+    for _ in 0..sys_clk() / 100_000 / CYCLES_PER_LOOP * us_10 {
         nop();
     }
 }
+
+/// ## Delay - SysTick 기반 지연
+/// nop 루프와 달리 flash wait state 나 컴파일러 최적화에 영향을 받지 않는다. <br/>
+/// `hclk` 는 `Clocks::hclk()` 값. 클럭을 바꾸면 `ClockDependent` 로 갱신한다. <br/>
+/// `hal` feature 에서는 `embedded_hal::delay::DelayNs` 를 구현한다.
+pub struct Delay {
+    systick: SysTick,
+    hclk: Cell<u32>,
+}
+
+impl Delay {
+    pub fn new(systick: SysTick, hclk: u32) -> Delay {
+        Delay {
+            systick,
+            hclk: Cell::new(hclk),
+        }
+    }
+
+    /// `amount` x (1 / `per_second`) 초 → HCLK tick. 올림 계산: 요청한 시간보다 짧게 기다리지 않는다
+    fn ticks(&self, amount: u32, per_second: u64) -> u64 {
+        (amount as u64 * self.hclk.get() as u64).div_ceil(per_second)
+    }
+
+    pub fn delay_ns(&self, ns: u32) {
+        self.systick.wait_ticks(self.ticks(ns, 1_000_000_000));
+    }
+
+    pub fn delay_us(&self, us: u32) {
+        self.systick.wait_ticks(self.ticks(us, 1_000_000));
+    }
+
+    pub fn delay_ms(&self, ms: u32) {
+        self.systick.wait_ticks(self.ticks(ms, 1_000));
+    }
+}
+
+impl ClockDependent for Delay {
+    fn clocks_changed(&self, clocks: &Clocks) {
+        self.hclk.set(clocks.hclk());
+    }
+}
+
+#[cfg(feature = "hal")]
+impl embedded_hal::delay::DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        Delay::delay_ns(self, ns);
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        Delay::delay_us(self, us);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        Delay::delay_ms(self, ms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::peripherals::register::mock;
+    use crate::peripherals::systick::SYSTICK_BASE;
+    use crate::peripherals::Peripherals;
+
+    #[test]
+    fn delay_counts_hclk_ticks() {
+        mock::reset();
+        mock::on_write(SYSTICK_BASE, |v| if v & 1 != 0 { v | (1 << 16) } else { v });
+        let delay = Delay::new(unsafe { Peripherals::steal() }.systick, 72_000_000);
+        delay.delay_ms(1);
+        delay.delay_us(10);
+        delay.delay_ns(100); // 7.2 tick -> 8
+        assert_eq!(mock::writes(SYSTICK_BASE + 0x04), [71_999, 719, 7]);
+    }
+
+    #[test]
+    fn delay_rounds_up_when_hclk_is_not_whole_mhz() {
+        mock::reset();
+        mock::on_write(SYSTICK_BASE, |v| if v & 1 != 0 { v | (1 << 16) } else { v });
+        let delay = Delay::new(unsafe { Peripherals::steal() }.systick, 1_500_500);
+        delay.delay_us(3); // 4.5015 tick -> 5
+        delay.delay_ms(1); // 1500.5 tick -> 1501
+        assert_eq!(mock::writes(SYSTICK_BASE + 0x04), [4, 1500]);
+    }
+}
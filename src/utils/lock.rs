@@ -0,0 +1,180 @@
+//! ## lock - BASEPRI priority ceiling
+//! `critical_section::with` (PRIMASK) 는 모든 인터럽트를 막는다. 여기서는 BASEPRI 를 올려 <br/>
+//! priority 가 ceiling 이하인 (숫자가 ceiling 이상인) 인터럽트만 막고, 더 높은 IRQ 는 계속 실행되게 한다. <br/>
+//! BASEPRI 는 group (preemption) priority 만 비교하므로 `PriorityGrouping` 의 sub priority 비트는 무시된다. <br/>
+//! ceiling 0 은 BASEPRI 로 표현할 수 없으므로 (0 = 마스킹 없음) PRIMASK critical section 을 쓴다.
+//! ```ignore
+//! // TIM2, EXTI15_10 모두 priority 2 이하에서 접근
+//! static READING: PriorityCell<u16, 2> = unsafe { PriorityCell::new(0) };
+//!
+//! nvic.set_priority(Interrupt::TIM2, 2);
+//! nvic.set_priority(Interrupt::EXTI15_10, 3);
+//!
+//! READING.lock(|r| *r = adc.read());       // TIM2, priority 2 이상의 IRQ 만 잠깐 막힘
+//! let r = READING.lock(|r| *r);            // main (thread mode)
+//! ```
+use core::{
+    cell::{Cell, UnsafeCell},
+    sync::atomic::{compiler_fence, Ordering},
+};
+
+use crate::peripherals::nvic::NVIC_PRIO_BITS;
+
+/// 가장 낮은 priority
+pub const LOWEST_PRIORITY: u8 = (1 << NVIC_PRIO_BITS) - 1;
+
+/// NVIC priority (0..=15) → BASEPRI 값 (상위 4 bit)
+const fn basepri_value(priority: u8) -> u8 {
+    priority << (8 - NVIC_PRIO_BITS)
+}
+
+#[cfg(not(test))]
+mod basepri {
+    pub fn read() -> u8 {
+        cortex_m::register::basepri::read()
+    }
+
+    /// BASEPRI_MAX: 지금보다 높은 priority 로만 올라간다 (낮추지 않음)
+    pub fn raise(value: u8) {
+        cortex_m::register::basepri_max::write(value);
+    }
+
+    pub fn restore(value: u8) {
+        unsafe { cortex_m::register::basepri::write(value) }
+    }
+}
+
+/// 호스트 테스트용 BASEPRI (thread 별)
+#[cfg(test)]
+mod basepri {
+    use std::cell::Cell;
+
+    thread_local! {
+        static BASEPRI: Cell<u8> = const { Cell::new(0) };
+    }
+
+    pub fn read() -> u8 {
+        BASEPRI.with(|b| b.get())
+    }
+
+    pub fn raise(value: u8) {
+        let current = read();
+        if value != 0 && (current == 0 || value < current) {
+            restore(value);
+        }
+    }
+
+    pub fn restore(value: u8) {
+        BASEPRI.with(|b| b.set(value));
+    }
+}
+
+/// ## with_priority_ceiling
+/// priority `ceiling` (0..=15) 이하의 인터럽트를 막은 채 `f` 를 실행하고 BASEPRI 를 되돌린다. <br/>
+/// 이미 더 높은 ceiling 안에 있으면 BASEPRI 는 그대로다 (중첩 가능).
+pub fn with_priority_ceiling<R>(ceiling: u8, f: impl FnOnce() -> R) -> R {
+    assert!(ceiling <= LOWEST_PRIORITY, "priority ceiling must be 0..=15");
+    if ceiling == 0 {
+        return critical_section::with(|_| f());
+    }
+    let previous = basepri::read();
+    basepri::raise(basepri_value(ceiling));
+    compiler_fence(Ordering::SeqCst);
+    let result = f();
+    compiler_fence(Ordering::SeqCst);
+    basepri::restore(previous);
+    result
+}
+
+/// ## PriorityCell - priority ceiling 으로 보호되는 값
+/// 센서 측정값, LCD 문자열 버퍼처럼 atomic 으로 다룰 수 없는 데이터를 main 과 인터럽트가 나눠 쓸 때 사용한다. <br/>
+/// `lock` 동안 priority `CEILING` 이하의 인터럽트만 막히고, 더 높은 IRQ (예: 통신 수신) 는 계속 실행된다. <br/>
+/// `lock` 안에서 같은 cell 을 다시 `lock` 하면 panic.
+pub struct PriorityCell<T, const CEILING: u8> {
+    value: UnsafeCell<T>,
+    locked: Cell<bool>,
+}
+
+// SAFETY: 접근은 `lock` 을 통해서만 일어나고, `new` 의 조건에 따라 접근하는 코드는 모두
+// CEILING 이하의 priority 에서 실행되므로 BASEPRI 가 올라가 있는 동안 다른 접근자가 끼어들 수 없다.
+unsafe impl<T: Send, const CEILING: u8> Sync for PriorityCell<T, CEILING> {}
+
+impl<T, const CEILING: u8> PriorityCell<T, CEILING> {
+    const VALID_CEILING: () = assert!(CEILING <= LOWEST_PRIORITY, "priority ceiling must be 0..=15");
+
+    /// ## new
+    /// # Safety
+    /// 이 cell 에 접근하는 모든 인터럽트의 priority 가 `CEILING` 이하 (숫자가 `CEILING` 이상) 여야 한다. <br/>
+    /// 더 높은 priority 의 핸들러가 `lock` 하면 BASEPRI 로 막을 수 없어 데이터 경합이 생긴다. <br/>
+    /// NMI / HardFault 에서는 접근하지 않는다.
+    pub const unsafe fn new(value: T) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID_CEILING;
+        PriorityCell {
+            value: UnsafeCell::new(value),
+            locked: Cell::new(false),
+        }
+    }
+
+    pub const fn ceiling(&self) -> u8 {
+        CEILING
+    }
+
+    /// ## lock
+    /// ceiling 까지 BASEPRI 를 올리고 `f` 에 값을 빌려준다.
+    pub fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        with_priority_ceiling(CEILING, || {
+            assert!(!self.locked.replace(true), "PriorityCell locked twice");
+            // SAFETY: ceiling 안에서 locked 가 false 였으므로 다른 참조가 없다
+            let result = f(unsafe { &mut *self.value.get() });
+            self.locked.set(false);
+            result
+        })
+    }
+}
+
+impl<T: Copy, const CEILING: u8> PriorityCell<T, CEILING> {
+    pub fn get(&self) -> T {
+        self.lock(|value| *value)
+    }
+
+    pub fn set(&self, value: T) {
+        self.lock(|v| *v = value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ceiling_raises_and_restores_basepri() {
+        assert_eq!(basepri::read(), 0);
+        with_priority_ceiling(5, || {
+            assert_eq!(basepri::read(), 0x50);
+            // 낮은 ceiling 안에서 높은 ceiling 으로만 올라간다
+            with_priority_ceiling(2, || assert_eq!(basepri::read(), 0x20));
+            with_priority_ceiling(9, || assert_eq!(basepri::read(), 0x50));
+            assert_eq!(basepri::read(), 0x50);
+        });
+        assert_eq!(basepri::read(), 0);
+    }
+
+    #[test]
+    fn priority_cell_locks_at_its_ceiling() {
+        static TEXT: PriorityCell<[u8; 4], 3> = unsafe { PriorityCell::new(*b"    ") };
+        TEXT.lock(|text| {
+            assert_eq!(basepri::read(), 0x30);
+            text.copy_from_slice(b"42 C");
+        });
+        assert_eq!(TEXT.get(), *b"42 C");
+        assert_eq!(basepri::read(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "locked twice")]
+    fn nested_lock_panics() {
+        let cell: PriorityCell<u32, 4> = unsafe { PriorityCell::new(0) };
+        cell.lock(|_| cell.lock(|_| ()));
+    }
+}
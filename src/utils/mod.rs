@@ -1 +1,4 @@
-pub mod delay;
\ No newline at end of file
+pub mod delay;
+pub mod shared;
+pub mod button;
+pub mod lock;
@@ -0,0 +1,64 @@
+//! ## shared - main 과 인터럽트 핸들러가 peripheral 을 나눠 쓰는 통로
+//! `Peripherals::take()` 로 얻은 드라이버를 main 에서 설정한 뒤 `put` 으로 넘기면 <br/>
+//! 핸들러는 `with` 안에서만 접근한다. 모든 접근이 critical section 안에서 일어나므로 <br/>
+//! main 과 핸들러의 read-modify-write 가 섞이지 않는다.
+//! ```ignore
+//! static EXTI: Shared<exti> = Shared::new();
+//!
+//! // main
+//! EXTI.put(dp.exti);
+//!
+//! // interrupt handler
+//! EXTI.with(|exti| exti.pr_clear(13));
+//! ```
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+pub struct Shared<T> {
+    inner: Mutex<RefCell<Option<T>>>,
+}
+
+impl<T> Shared<T> {
+    pub const fn new() -> Shared<T> {
+        Shared {
+            inner: Mutex::new(RefCell::new(None)),
+        }
+    }
+
+    /// 값을 넣는다. 이미 들어 있던 값은 돌려준다.
+    pub fn put(&self, value: T) -> Option<T> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).replace(value))
+    }
+
+    /// 값을 꺼내 다시 main 이 소유한다
+    pub fn take(&self) -> Option<T> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).take())
+    }
+
+    /// ## with
+    /// critical section 안에서 `f` 를 실행한다. 아직 `put` 하지 않았으면 `None`. <br/>
+    /// `f` 안에서 같은 `Shared` 의 `with` 를 다시 부르면 panic (RefCell 이중 borrow).
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).as_mut().map(f))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_runs_only_after_put() {
+        let shared: Shared<u32> = Shared::new();
+        assert_eq!(shared.with(|v| *v), None);
+        assert_eq!(shared.put(1), None);
+        assert_eq!(shared.with(|v| {
+            *v += 1;
+            *v
+        }), Some(2));
+        assert_eq!(shared.put(10), Some(2));
+        assert_eq!(shared.take(), Some(10));
+        assert_eq!(shared.take(), None);
+    }
+}